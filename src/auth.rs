@@ -1,22 +1,26 @@
 use crate::{
     config,
+    csrf::Csrf,
     db::{
         defs::{DBQuery, DBTable, SharedDB},
-        table::User,
+        table::{AccessGrant, Record, Scope, Session, User},
     },
+    keys::keys,
+    miscs::generate_token,
     ClientError, Error, Result,
 };
 
-use async_graphql::Context;
+use async_graphql::{Context, Object};
+use axum::http::header;
 use axum_extra::{
-    headers::{authorization::Bearer, Authorization},
+    headers::{authorization::Bearer, Authorization, UserAgent},
     TypedHeader,
 };
 use chrono::{Duration, Utc};
 use cookie::Cookie;
-use jsonwebtoken::{errors::ErrorKind, DecodingKey, EncodingKey, Header};
-use serde::{Deserialize, Serialize};
-use surrealdb::sql::Thing;
+use jsonwebtoken::{errors::ErrorKind, Validation};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
 use tower_cookies::Cookies;
 use tracing::Instrument;
 
@@ -24,32 +28,84 @@ use tracing::Instrument;
 pub struct Claims {
     exp: i64,
     sub: String,
+    sid: String,
 }
 
 impl Claims {
     pub fn sub(&self) -> &str {
         &self.sub
     }
+
+    pub fn sid(&self) -> &str {
+        &self.sid
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    exp: i64,
+    sub: String,
+    aud: String,
+    remember_me: bool,
+}
+
+impl ChallengeClaims {
+    pub fn sub(&self) -> &str {
+        &self.sub
+    }
+
+    pub fn remember_me(&self) -> bool {
+        self.remember_me
+    }
+}
+
+pub struct Tokens {
+    access_token: String,
+    refresh_token: String,
+}
+
+#[Object]
+impl Tokens {
+    async fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    async fn refresh_token(&self) -> &str {
+        &self.refresh_token
+    }
 }
 
 pub struct Auth;
 
 impl Auth {
     pub const COOKIE_NAME: &'static str = "connect.sid";
+    pub const REFRESH_COOKIE_NAME: &'static str = "connect.rid";
+
+    const ACCESS_TOKEN_MINUTES: i64 = 15;
+    const REFRESH_TOKEN_LENGTH: usize = 64;
+    const SESSION_DAYS: i64 = 1;
+    const REMEMBER_ME_SESSION_DAYS: i64 = 30;
 
-    pub async fn generate_jwt(thing: &Thing) -> Result<String> {
+    const ACCESS_TOKEN_PREFIX: &'static str = "bsh_";
+    const ACCESS_TOKEN_LENGTH: usize = 48;
+
+    const CHALLENGE_AUDIENCE: &'static str = "two_factor";
+    const CHALLENGE_MINUTES: i64 = 5;
+
+    pub async fn generate_jwt(thing: &Thing, session: &Thing) -> Result<String> {
         // Temporary
         tracing::debug!(%thing, "Generating JWT");
 
         let claims = Claims {
-            exp: (Utc::now() + Duration::days(30)).timestamp(),
+            exp: (Utc::now() + Duration::minutes(Self::ACCESS_TOKEN_MINUTES)).timestamp(),
             sub: thing.id.to_string(),
+            sid: session.id.to_string(),
         };
 
         Ok(jsonwebtoken::encode(
-            &Header::default(),
+            &keys().header(),
             &claims,
-            &EncodingKey::from_secret(config().JWT_SECRET.as_bytes()),
+            keys().signing(),
         )?)
     }
 
@@ -57,89 +113,429 @@ impl Auth {
         // Temporary
         tracing::debug!("Validating JWT");
 
-        let claims = match jsonwebtoken::decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(config().JWT_SECRET.as_bytes()),
-            &Default::default(),
-        ) {
-            Ok(data) => data.claims,
+        let claims = Self::decode::<Claims>(token, None)?;
+
+        // Temporary
+        tracing::debug!(subject = %claims.sub, "JWT Validated");
+
+        Ok(claims)
+    }
+
+    pub async fn generate_challenge(thing: &Thing, remember_me: bool) -> Result<String> {
+        // Temporary
+        tracing::debug!(%thing, "Generating challenge");
+
+        let claims = ChallengeClaims {
+            exp: (Utc::now() + Duration::minutes(Self::CHALLENGE_MINUTES)).timestamp(),
+            sub: thing.id.to_string(),
+            aud: Self::CHALLENGE_AUDIENCE.to_string(),
+            remember_me,
+        };
+
+        Ok(jsonwebtoken::encode(
+            &keys().header(),
+            &claims,
+            keys().signing(),
+        )?)
+    }
+
+    pub async fn validate_challenge(token: &str) -> Result<ChallengeClaims> {
+        // Temporary
+        tracing::debug!("Validating challenge");
+
+        let claims = Self::decode::<ChallengeClaims>(token, Some(Self::CHALLENGE_AUDIENCE))?;
+
+        // Temporary
+        tracing::debug!(subject = %claims.sub, "Challenge validated");
+
+        Ok(claims)
+    }
+
+    fn decode<T: DeserializeOwned>(token: &str, audience: Option<&str>) -> Result<T> {
+        let decoded = jsonwebtoken::decode_header(token).and_then(|header| {
+            let Some((algorithm, key)) = keys().verifying(header.kid.as_deref()) else {
+                return Err(ErrorKind::InvalidKeyFormat.into());
+            };
+
+            let mut validation = Validation::new(algorithm);
+
+            if let Some(audience) = audience {
+                validation.set_audience(&[audience]);
+            }
+
+            jsonwebtoken::decode::<T>(token, key, &validation)
+        });
+
+        match decoded {
+            Ok(data) => Ok(data.claims),
             Err(err) => {
                 // Temporary
                 tracing::debug!(%err, "JWT Validation failed");
 
                 match err.kind() {
-                    ErrorKind::ExpiredSignature | ErrorKind::InvalidToken => {
-                        return Err(Error::Client(ClientError::Unauthorized));
-                    }
-                    _ => {
-                        return Err(Error::JsonWebToken(err));
-                    }
+                    ErrorKind::ExpiredSignature
+                    | ErrorKind::InvalidToken
+                    | ErrorKind::InvalidSignature
+                    | ErrorKind::InvalidAlgorithm
+                    | ErrorKind::InvalidAudience
+                    | ErrorKind::InvalidKeyFormat
+                    | ErrorKind::Base64(_)
+                    | ErrorKind::Utf8(_)
+                    | ErrorKind::Json(_) => Err(Error::Client(ClientError::Unauthorized)),
+                    _ => Err(Error::JsonWebToken(err)),
                 }
             }
-        };
+        }
+    }
 
-        // Temporary
-        tracing::debug!(subject = %claims.sub, "JWT Validated");
+    pub fn cookie(token: &str) -> Cookie<'_> {
+        let cookie = Cookie::build((Self::COOKIE_NAME, token))
+            .path("/")
+            .secure(true)
+            .expires(None)
+            .http_only(true)
+            .same_site(cookie::SameSite::None)
+            .max_age(cookie::time::Duration::minutes(Self::ACCESS_TOKEN_MINUTES));
 
-        Ok(claims)
+        cookie.build()
     }
 
-    pub fn cookie(token: &str) -> Cookie {
-        let cookie = Cookie::build((Self::COOKIE_NAME, token))
+    pub fn refresh_cookie(token: &str) -> Cookie<'_> {
+        let cookie = Cookie::build((Self::REFRESH_COOKIE_NAME, token))
             .path("/")
             .secure(true)
             .expires(None)
             .http_only(true)
             .same_site(cookie::SameSite::None)
-            .max_age(cookie::time::Duration::days(30));
+            .max_age(cookie::time::Duration::days(Self::REMEMBER_ME_SESSION_DAYS));
 
         cookie.build()
     }
+
+    pub fn append_cookies(ctx: &Context<'_>, tokens: &Tokens) {
+        let cookie = Self::cookie(&tokens.access_token);
+        let refresh_cookie = Self::refresh_cookie(&tokens.refresh_token);
+
+        ctx.append_http_header(header::SET_COOKIE, cookie.to_string());
+        ctx.append_http_header(header::SET_COOKIE, refresh_cookie.to_string());
+        ctx.append_http_header(
+            header::SET_COOKIE,
+            Csrf::cookie(&Csrf::generate()).to_string(),
+        );
+    }
+
+    pub fn add_cookies(cookies: &Cookies, tokens: &Tokens) {
+        cookies.add(Self::cookie(&tokens.access_token).into_owned());
+        cookies.add(Self::refresh_cookie(&tokens.refresh_token).into_owned());
+        cookies.add(Csrf::cookie(&Csrf::generate()).into_owned());
+    }
+
+    pub fn remove_cookies(ctx: &Context<'_>) -> Result<bool> {
+        let cookies = ctx.data::<Cookies>()?;
+
+        let mut removed = false;
+
+        for name in [Self::COOKIE_NAME, Self::REFRESH_COOKIE_NAME] {
+            let Some(mut cookie) = cookies.get(name) else {
+                continue;
+            };
+
+            cookie.set_max_age(cookie::time::Duration::ZERO);
+
+            ctx.append_http_header(header::SET_COOKIE, cookie.to_string());
+
+            removed = true;
+        }
+
+        if let Some(mut cookie) = cookies.get(Csrf::COOKIE_NAME) {
+            cookie.set_max_age(cookie::time::Duration::ZERO);
+
+            ctx.append_http_header(header::SET_COOKIE, cookie.to_string());
+        }
+
+        Ok(removed)
+    }
+
+    fn user_agent(ctx: &Context<'_>) -> Option<String> {
+        ctx.data::<Option<TypedHeader<UserAgent>>>()
+            .ok()?
+            .as_ref()
+            .map(|header| header.0.as_str().to_string())
+    }
 }
 
 impl Auth {
-    pub async fn authenticate(ctx: &Context<'_>) -> Result<User> {
+    pub async fn create_session(
+        ctx: &Context<'_>,
+        user: &Thing,
+        remember_me: bool,
+    ) -> Result<Tokens> {
         let db = ctx.data::<SharedDB>()?;
+
+        Self::open_session(db, user, remember_me, Self::user_agent(ctx)).await
+    }
+
+    pub async fn open_session(
+        db: &SharedDB,
+        user: &Thing,
+        remember_me: bool,
+        user_agent: Option<String>,
+    ) -> Result<Tokens> {
+        // Temporary
+        tracing::debug!("Creating session");
+
+        let days = match remember_me {
+            true => Self::REMEMBER_ME_SESSION_DAYS,
+            false => Self::SESSION_DAYS,
+        };
+
+        let refresh_token = generate_token(Self::REFRESH_TOKEN_LENGTH);
+
+        let mut response = db
+            .query(DBQuery::CREATE_SESSION)
+            .bind(("user", user.to_owned()))
+            .bind(("refresh", refresh_token.to_owned()))
+            .bind(("remember_me", remember_me))
+            .bind(("user_agent", user_agent))
+            .bind((
+                "expires_at",
+                Datetime::from(Utc::now() + Duration::days(days)),
+            ))
+            .await?;
+
+        let Some(session) = response.take::<Option<Record>>(0)? else {
+            return Err(Error::RecordNotCreated(DBTable::SESSION.to_string()));
+        };
+
+        // Temporary
+        tracing::debug!(session = %session.id(), "Session created");
+
+        Ok(Tokens {
+            access_token: Self::generate_jwt(user, session.id()).await?,
+            refresh_token,
+        })
+    }
+
+    pub async fn refresh_session(db: &SharedDB, refresh_token: &str) -> Result<(Session, Tokens)> {
+        // Temporary
+        tracing::debug!("Refreshing session");
+
+        let new_refresh_token = generate_token(Self::REFRESH_TOKEN_LENGTH);
+
+        let mut response = db
+            .query(DBQuery::REFRESH_SESSION)
+            .bind(("refresh", refresh_token.to_owned()))
+            .bind(("new_refresh", new_refresh_token.to_owned()))
+            .await?;
+
+        let Some(session) = response.take::<Option<Session>>(0)? else {
+            // Temporary
+            tracing::debug!("Session not refreshed");
+
+            return Err(Error::Client(ClientError::Unauthorized));
+        };
+
+        // Temporary
+        tracing::debug!(session = %session.id(), "Session refreshed");
+
+        let tokens = Tokens {
+            access_token: Self::generate_jwt(session.user(), session.id()).await?,
+            refresh_token: new_refresh_token,
+        };
+
+        Ok((session, tokens))
+    }
+
+    pub async fn revoke_session(db: &SharedDB, claims: &Claims) -> Result<()> {
+        // Temporary
+        tracing::debug!(session = %claims.sid(), "Revoking session");
+
+        db.query(DBQuery::REVOKE_SESSION)
+            .bind((
+                "user_session",
+                Thing::from((DBTable::SESSION, claims.sid())),
+            ))
+            .bind(("user", Thing::from((DBTable::USER, claims.sub()))))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub async fn revoke_refresh_token(db: &SharedDB, refresh_token: &str) -> Result<()> {
+        // Temporary
+        tracing::debug!("Revoking session from refresh token");
+
+        db.query(DBQuery::REVOKE_SESSION_FROM_REFRESH)
+            .bind(("refresh", refresh_token.to_owned()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+}
+
+impl Auth {
+    pub async fn claims(ctx: &Context<'_>) -> Result<Claims> {
         let cookies = ctx.data::<Cookies>()?;
         let auth_header = ctx.data::<Option<TypedHeader<Authorization<Bearer>>>>()?;
 
+        // Bearer tokens take precedence so that requests exempt from CSRF checks never
+        // fall back to the ambient session cookie.
+        let token = auth_header
+            .as_ref()
+            .map(|header| header.0.token().to_string())
+            .unwrap_or_else(|| {
+                cookies
+                    .get(Auth::COOKIE_NAME)
+                    .map(|cookie| cookie.value().to_string())
+                    .unwrap_or_default()
+            });
+
+        // Temporary
+        tracing::debug!("Token found");
+
+        Auth::validate_jwt(&token).await
+    }
+
+    pub async fn authenticate(ctx: &Context<'_>) -> Result<User> {
+        Self::authorize(ctx, Scope::Read).await
+    }
+
+    pub async fn authenticate_account(ctx: &Context<'_>) -> Result<User> {
+        Ok(Self::authenticate_session(ctx).await?.0)
+    }
+
+    pub async fn authorize(ctx: &Context<'_>, scope: Scope) -> Result<User> {
+        let auth_header = ctx.data::<Option<TypedHeader<Authorization<Bearer>>>>()?;
+
+        let access_token = auth_header
+            .as_ref()
+            .map(|header| header.0.token())
+            .filter(|token| token.starts_with(Self::ACCESS_TOKEN_PREFIX));
+
+        match access_token {
+            Some(token) => Self::authenticate_access_token(ctx, token, scope).await,
+            None => Self::authenticate_account(ctx).await,
+        }
+    }
+
+    pub fn generate_access_token() -> String {
+        format!(
+            "{}{}",
+            Self::ACCESS_TOKEN_PREFIX,
+            generate_token(Self::ACCESS_TOKEN_LENGTH)
+        )
+    }
+
+    async fn authenticate_access_token(
+        ctx: &Context<'_>,
+        token: &str,
+        scope: Scope,
+    ) -> Result<User> {
+        let db = ctx.data::<SharedDB>()?;
+
         let future = async {
             // Temporary
-            tracing::debug!("Authentication");
+            tracing::debug!(?scope, "Authenticating access token");
+
+            let mut response = db
+                .query(DBQuery::SELECT_USER_FROM_ACCESS_TOKEN)
+                .bind(("access_token", token.to_owned()))
+                .await?;
+
+            let Some(grant) = response.take::<Option<AccessGrant>>(0)? else {
+                // Temporary
+                tracing::debug!("Access token not found");
+
+                return Err(Error::Client(ClientError::Unauthorized));
+            };
+
+            if !grant.allows(scope) {
+                // Temporary
+                tracing::debug!("Scope not granted");
+
+                return Err(Error::Client(ClientError::Forbidden));
+            }
+
+            let user = grant.user();
+
+            if user.is_banned() {
+                // Temporary
+                tracing::debug!("User banned");
+
+                return Err(Error::Client(ClientError::Banned));
+            }
+
+            Ok(user)
+        };
+
+        let span = tracing::debug_span!("Auth");
 
-            let token = cookies
-                .get(Auth::COOKIE_NAME)
-                .map(|cookie| cookie.value().to_string())
-                .unwrap_or_else(|| {
-                    auth_header
-                        .as_ref()
-                        .map(|header| header.0.token().to_string())
-                        .unwrap_or_default()
-                });
+        future.instrument(span).await
+    }
+
+    pub fn ensure_verified(user: &User) -> Result<()> {
+        if user.is_guest() {
+            if !config().ALLOW_GUESTS {
+                // Temporary
+                tracing::debug!("Guest posting disabled");
+
+                return Err(Error::Client(ClientError::Unauthorized));
+            }
+
+            return Ok(());
+        }
+
+        if config().REQUIRE_VERIFIED_EMAIL && !user.is_verified() {
+            // Temporary
+            tracing::debug!("Email not verified");
+
+            return Err(Error::Client(ClientError::EmailNotVerified));
+        }
+
+        Ok(())
+    }
+
+    pub async fn authenticate_session(ctx: &Context<'_>) -> Result<(User, Claims)> {
+        let db = ctx.data::<SharedDB>()?;
 
+        let future = async {
             // Temporary
-            tracing::debug!("Token found");
+            tracing::debug!("Authentication");
 
-            let claims = Auth::validate_jwt(&token).await?;
+            let claims = Auth::claims(ctx).await?;
 
             // Temporary
             tracing::debug!(subject = %claims.sub(), "Authenticating");
 
             let user = Thing::from((DBTable::USER, claims.sub()));
+            let session = Thing::from((DBTable::SESSION, claims.sid()));
 
-            let mut response = db.query(DBQuery::SELECT_ID).bind(("thing", user)).await?;
+            let mut response = db
+                .query(DBQuery::SELECT_USER_FROM_SESSION)
+                .bind(("user", user))
+                .bind(("user_session", session))
+                .await?;
 
             let Some(user) = response.take::<Option<User>>(0)? else {
                 // Temporary
-                tracing::debug!("User not found");
+                tracing::debug!("User or session not found");
 
                 return Err(Error::Client(ClientError::Unauthorized));
             };
 
+            if user.is_banned() {
+                // Temporary
+                tracing::debug!("User banned");
+
+                return Err(Error::Client(ClientError::Banned));
+            }
+
             // Temporary
             tracing::debug!("User found");
 
-            Ok(user)
+            Ok((user, claims))
         };
 
         let span = tracing::debug_span!("Auth");
@@ -147,3 +543,119 @@ impl Auth {
         future.instrument(span).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn thing(table: &str, id: &str) -> Thing {
+        Thing::from((table, id))
+    }
+
+    #[tokio::test]
+    async fn access_token_round_trips() {
+        test_utils::init();
+
+        let token = Auth::generate_jwt(
+            &thing(DBTable::USER, "alice"),
+            &thing(DBTable::SESSION, "one"),
+        )
+        .await
+        .unwrap();
+
+        let claims = Auth::validate_jwt(&token).await.unwrap();
+
+        assert_eq!(claims.sub(), "alice");
+        assert_eq!(claims.sid(), "one");
+    }
+
+    #[tokio::test]
+    async fn tampered_and_expired_tokens_are_rejected() {
+        test_utils::init();
+
+        let token = Auth::generate_jwt(
+            &thing(DBTable::USER, "alice"),
+            &thing(DBTable::SESSION, "one"),
+        )
+        .await
+        .unwrap();
+
+        let tampered = format!("{token}x");
+
+        assert!(matches!(
+            Auth::validate_jwt(&tampered).await,
+            Err(Error::Client(ClientError::Unauthorized))
+        ));
+
+        let expired = Claims {
+            exp: (Utc::now() - Duration::minutes(5)).timestamp(),
+            sub: "alice".to_string(),
+            sid: "one".to_string(),
+        };
+
+        let expired = jsonwebtoken::encode(&keys().header(), &expired, keys().signing()).unwrap();
+
+        assert!(matches!(
+            Auth::validate_jwt(&expired).await,
+            Err(Error::Client(ClientError::Unauthorized))
+        ));
+    }
+
+    #[tokio::test]
+    async fn challenge_and_access_tokens_are_not_interchangeable() {
+        test_utils::init();
+
+        let user = thing(DBTable::USER, "alice");
+
+        let challenge = Auth::generate_challenge(&user, true).await.unwrap();
+        let access = Auth::generate_jwt(&user, &thing(DBTable::SESSION, "one"))
+            .await
+            .unwrap();
+
+        assert!(Auth::validate_jwt(&challenge).await.is_err());
+        assert!(Auth::validate_challenge(&access).await.is_err());
+
+        let claims = Auth::validate_challenge(&challenge).await.unwrap();
+
+        assert_eq!(claims.sub(), "alice");
+        assert!(claims.remember_me());
+    }
+
+    #[test]
+    fn session_cookies_are_http_only() {
+        for cookie in [Auth::cookie("token"), Auth::refresh_cookie("token")] {
+            assert_eq!(cookie.http_only(), Some(true));
+            assert_eq!(cookie.secure(), Some(true));
+        }
+    }
+
+    #[tokio::test]
+    async fn refresh_rotates_and_detects_reuse() {
+        let db = test_utils::db().await;
+
+        let user = test_utils::user(&db, "").await;
+
+        let first = Auth::open_session(&db, user.id(), false, None)
+            .await
+            .unwrap();
+
+        let (session, second) = Auth::refresh_session(&db, &first.refresh_token)
+            .await
+            .unwrap();
+
+        assert_eq!(session.user(), user.id());
+        assert_ne!(first.refresh_token, second.refresh_token);
+
+        // Replaying a rotated refresh token is treated as theft and revokes the
+        // whole session, including the token that replaced it.
+        assert!(matches!(
+            Auth::refresh_session(&db, &first.refresh_token).await,
+            Err(Error::Client(ClientError::Unauthorized))
+        ));
+        assert!(matches!(
+            Auth::refresh_session(&db, &second.refresh_token).await,
+            Err(Error::Client(ClientError::Unauthorized))
+        ));
+    }
+}
@@ -13,13 +13,54 @@ pub fn config() -> &'static Config {
 
 #[allow(non_snake_case)]
 pub struct Config {
-    pub JWT_SECRET: String,
+    pub JWT_SECRET: Option<String>,
+    pub JWT_ALGORITHM: String,
+    pub JWT_KID: Option<String>,
+    pub JWT_PRIVATE_KEY: Option<String>,
+    pub JWT_JWKS: Option<String>,
+    pub APP_URL: String,
+    pub REQUIRE_VERIFIED_EMAIL: bool,
+    pub ALLOW_GUESTS: bool,
+    pub HIDE_ACCOUNT_EXISTENCE: bool,
+    pub TRUST_PROXY: bool,
+    pub ARGON2_MEMORY_KIB: u32,
+    pub ARGON2_ITERATIONS: u32,
+    pub ARGON2_PARALLELISM: u32,
 }
 
 impl Config {
     fn load_from_env() -> Result<Self> {
         Ok(Self {
-            JWT_SECRET: get_env("JWT_SECRET")?,
+            JWT_SECRET: get_env("JWT_SECRET").ok(),
+            JWT_ALGORITHM: get_env("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()),
+            JWT_KID: get_env("JWT_KID").ok(),
+            JWT_PRIVATE_KEY: get_env("JWT_PRIVATE_KEY").ok(),
+            JWT_JWKS: get_env("JWT_JWKS").ok(),
+            APP_URL: get_env("APP_URL").unwrap_or_else(|_| "https://basher.dcism.org".to_string()),
+            REQUIRE_VERIFIED_EMAIL: get_env("REQUIRE_VERIFIED_EMAIL")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+            ALLOW_GUESTS: get_env("ALLOW_GUESTS")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+            HIDE_ACCOUNT_EXISTENCE: get_env("HIDE_ACCOUNT_EXISTENCE")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+            TRUST_PROXY: get_env("TRUST_PROXY")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+            ARGON2_MEMORY_KIB: get_env("ARGON2_MEMORY_KIB")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(argon2::Params::DEFAULT_M_COST),
+            ARGON2_ITERATIONS: get_env("ARGON2_ITERATIONS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(argon2::Params::DEFAULT_T_COST),
+            ARGON2_PARALLELISM: get_env("ARGON2_PARALLELISM")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(argon2::Params::DEFAULT_P_COST),
         })
     }
 }
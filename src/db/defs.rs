@@ -7,9 +7,13 @@ pub struct DBTable;
 
 impl DBTable {
     pub const TAG: &'static str = "tag";
+    pub const LOGIN_ATTEMPT: &'static str = "login_attempt";
     pub const USER: &'static str = "user";
     pub const TOPIC: &'static str = "topic";
     pub const REPLY: &'static str = "reply";
+    pub const SESSION: &'static str = "session";
+    pub const USER_SETTINGS: &'static str = "user_settings";
+    pub const ACCESS_TOKEN: &'static str = "access_token";
 }
 
 pub struct DBQuery;
@@ -21,7 +25,148 @@ impl DBQuery {
     "#;
 
     pub const SELECT_ONLY_USER_FROM_EMAIL: &'static str = r#"
-    SELECT * FROM ONLY user WHERE email = $email LIMIT 1;
+    SELECT * FROM ONLY user WHERE email = $email AND deleted_at = NONE LIMIT 1;
+    "#;
+
+    pub const SELECT_PROFILE: &'static str = r#"
+    SELECT
+        meta::id(id) AS id,
+        email,
+        verified,
+        guest,
+        role,
+        time.created_at AS created_at,
+        {
+            topics: count(SELECT * FROM wrote WHERE in = $user AND meta::tb(out) = 'topic'),
+            replies: count(SELECT * FROM wrote WHERE in = $user AND meta::tb(out) = 'reply'),
+            likes_received: math::sum(SELECT VALUE out.counter.likes FROM wrote WHERE in = $user)
+        } AS stats
+        OMIT time
+    FROM ONLY $user;
+    "#;
+
+    pub const SELECT_RECENT_SESSION: &'static str = r#"
+    SELECT VALUE id FROM ONLY $user_session WHERE user = $user AND time.created_at > $since LIMIT 1;
+    "#;
+
+    pub const SELECT_USER_FROM_SESSION: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    IF ((SELECT * FROM ONLY $user_session WHERE user = $user AND is_revoked = false AND expires_at > time::now() LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $user_session SET last_seen_at = time::now();
+
+    RETURN (SELECT * FROM ONLY $user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const SELECT_TOPIC_LOCK: &'static str = r#"
+    SELECT VALUE is_locked FROM ONLY $topic;
+    "#;
+
+    pub const SELECT_USER_FROM_IDENTITY: &'static str = r#"
+    SELECT VALUE out FROM ONLY $topic->user_identity WHERE identity = $identity LIMIT 1;
+    "#;
+
+    pub const SELECT_USER_FROM_ACCESS_TOKEN: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $entry = (SELECT * FROM ONLY access_token WHERE token = crypto::sha256($access_token) AND is_revoked = false AND (expires_at = NONE OR expires_at > time::now()) LIMIT 1);
+
+    IF $entry = NONE {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $entry.id SET last_used_at = time::now();
+
+    RETURN {
+        user: (SELECT * FROM ONLY $entry.user),
+        scopes: $entry.scopes
+    };
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const SELECT_ACCESS_TOKENS: &'static str = r#"
+    SELECT
+        meta::id(id) AS id,
+        name,
+        scopes,
+        last_used_at,
+        expires_at,
+        time.created_at AS created_at
+        OMIT time
+    FROM access_token
+    WHERE user = $user AND is_revoked = false AND (expires_at = NONE OR expires_at > time::now())
+    ORDER BY created_at DESC;
+    "#;
+
+    pub const SELECT_USER_SETTINGS: &'static str = r#"
+    SELECT * OMIT id, time FROM ONLY $settings;
+    "#;
+
+    pub const SELECT_LOCKED_LOGIN_ATTEMPTS: &'static str = r#"
+    SELECT VALUE locked_until FROM $attempts WHERE locked_until > time::now();
+    "#;
+
+    pub const SELECT_SESSIONS: &'static str = r#"
+    SELECT
+        meta::id(id) AS id,
+        remember_me,
+        user_agent,
+        last_seen_at,
+        time.created_at AS created_at,
+        (id = $user_session) AS is_current
+        OMIT time
+    FROM session
+    WHERE user = $user AND is_revoked = false AND expires_at > time::now()
+    ORDER BY last_seen_at DESC;
+    "#;
+
+    pub const SELECT_USER_EXPORT: &'static str = r#"
+    SELECT email, time.created_at AS created_at FROM ONLY $user;
+
+    SELECT
+        *,
+        meta::id(id) AS id,
+        time.created_at AS activity,
+        time::millis(time.created_at) AS created_at,
+        ((SELECT VALUE meta::id(out) FROM ->tag_line)) AS tags,
+        {
+            is_owner: true,
+            is_shared: ((SELECT * FROM ONLY id<-shares WHERE in = $user LIMIT 1) != NONE),
+            is_liked: ((SELECT * FROM ONLY id<-likes WHERE in = $user AND is_deleted = false LIMIT 1) != NONE),
+            identity: (SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $parent.id LIMIT 1)
+        } AS user_status
+        OMIT time
+    FROM $user->wrote->topic
+    ORDER BY created_at DESC
+    FETCH counter;
+
+    SELECT
+        *,
+        meta::id(id) AS id,
+        time.created_at AS activity,
+        time::millis(time.created_at) AS created_at,
+        (SELECT meta::id($parent.parent) AS id, identity AS user_identity FROM ONLY parent<-wrote<-user<-user_identity WHERE in IN $parent.id<-contains<-topic LIMIT 1) AS parent,
+        {
+            is_owner: true,
+            is_shared: ((SELECT * FROM ONLY id<-shares WHERE in = $user LIMIT 1) != NONE),
+            is_liked: ((SELECT * FROM ONLY id<-likes WHERE in = $user AND is_deleted = false LIMIT 1) != NONE),
+            identity: (SELECT VALUE identity FROM ONLY user_identity WHERE out = $user AND in IN $parent.id<-contains<-topic LIMIT 1)
+        } AS user_status
+        OMIT time
+    FROM $user->wrote.out
+    WHERE meta::tb(id) = "reply"
+    ORDER BY activity DESC
+    FETCH counter;
+
+    SELECT meta::id(out) AS id, meta::tb(out) AS class FROM likes WHERE in = $user AND is_deleted = false;
+
+    SELECT meta::id(out) AS id, meta::tb(out) AS class FROM shares WHERE in = $user;
     "#;
 
     pub const SELECT_TOPICS: &'static str = r#"
@@ -36,11 +181,13 @@ impl DBQuery {
             is_shared: ((SELECT * FROM ONLY id<-shares WHERE in = $user LIMIT 1) != NONE),
             is_liked: ((SELECT * FROM ONLY id<-likes WHERE in = $user AND is_deleted = false LIMIT 1) != NONE),
             identity: (SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $parent.id LIMIT 1)
-        } AS user_status
+        } AS user_status,
+        (IF $sort = 'popular' THEN counter.likes + counter.replies ELSE IF $sort = 'oldest' THEN -time::nano(time.created_at) ELSE time::nano(time.created_at) END) AS rank
         OMIT time
     FROM topic
-    ORDER BY created_at DESC
-    LIMIT 20
+    WHERE array::is_empty($hidden_tags) OR !(id->tag_line[WHERE meta::id(out) IN $hidden_tags])
+    ORDER BY rank DESC
+    LIMIT $limit
     START $offset
     FETCH counter;
     "#;
@@ -83,13 +230,67 @@ impl DBQuery {
             is_shared: ((SELECT * FROM ONLY id<-shares WHERE in = $user LIMIT 1) != NONE),
             is_liked: ((SELECT * FROM ONLY id<-likes WHERE in = $user AND is_deleted = false LIMIT 1) != NONE),
             identity: (SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $topic LIMIT 1)
-        } AS user_status
+        } AS user_status,
+        ((SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $topic LIMIT 1) IN (SELECT VALUE identity FROM muted_identity WHERE user = $user AND topic = $topic)) AS is_muted
         OMIT time
     FROM ONLY $reply
     LIMIT 1
     FETCH counter;
     "#;
 
+    pub const SELECT_TAGS_FROM_PREFIX: &'static str = r#"
+    SELECT
+        meta::id(id) AS name,
+        indexed
+    FROM tag
+    WHERE string::starts_with(meta::id(id), $prefix)
+    ORDER BY indexed DESC
+    LIMIT 10;
+    "#;
+
+    pub const SELECT_POPULAR_TAGS: &'static str = r#"
+    SELECT
+        meta::id(id) AS name,
+        indexed
+    FROM tag
+    ORDER BY indexed DESC
+    LIMIT 20
+    START $offset;
+    "#;
+
+    pub const SELECT_TRENDING_TAGS: &'static str = r#"
+    SELECT
+        meta::id(out) AS name,
+        out.indexed AS indexed,
+        recent,
+        recent - earlier AS growth
+    FROM (
+        SELECT
+            out,
+            count(time.created_at > $since) AS recent,
+            count(time.created_at <= $since) AS earlier
+        FROM tag_line
+        WHERE time.created_at > $previous
+        GROUP BY out
+    )
+    WHERE recent > 0
+    ORDER BY growth DESC, recent DESC
+    LIMIT 20;
+    "#;
+
+    pub const SELECT_REVISIONS: &'static str = r#"
+    SELECT
+        meta::id(id) AS id,
+        title,
+        content,
+        time.created_at AS created_at
+    FROM revision
+    WHERE record = $record
+    ORDER BY created_at DESC
+    LIMIT 10
+    START $offset;
+    "#;
+
     pub const SELECT_REPLIES_FROM_TOPIC: &'static str = r#"
     SELECT
         *,
@@ -102,7 +303,8 @@ impl DBQuery {
             is_shared: ((SELECT * FROM ONLY id<-shares WHERE in = $user LIMIT 1) != NONE),
             is_liked: ((SELECT * FROM ONLY id<-likes WHERE in = $user AND is_deleted = false LIMIT 1) != NONE),
             identity: (SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $topic LIMIT 1)
-        } AS user_status
+        } AS user_status,
+        ((SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $topic LIMIT 1) IN (SELECT VALUE identity FROM muted_identity WHERE user = $user AND topic = $topic)) AS is_muted
         OMIT time
     FROM $topic->contains.out
     ORDER BY created_at
@@ -123,7 +325,8 @@ impl DBQuery {
             is_shared: ((SELECT * FROM ONLY id<-shares WHERE in = $user LIMIT 1) != NONE),
             is_liked: ((SELECT * FROM ONLY id<-likes WHERE in = $user AND is_deleted = false LIMIT 1) != NONE),
             identity: (SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $topic LIMIT 1)
-        } AS user_status
+        } AS user_status,
+        ((SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $topic LIMIT 1) IN (SELECT VALUE identity FROM muted_identity WHERE user = $user AND topic = $topic)) AS is_muted
         OMIT time
     FROM reply
     WHERE parent = $reply
@@ -146,12 +349,14 @@ impl DBQuery {
             is_shared: ((SELECT * FROM ONLY id<-shares WHERE in = $user LIMIT 1) != NONE),
             is_liked: ((SELECT * FROM ONLY id<-likes WHERE in = $user AND is_deleted = false LIMIT 1) != NONE),
             identity: (SELECT VALUE identity FROM ONLY id<-wrote<-user<-user_identity WHERE in = $parent.id LIMIT 1)
-        } AS user_status
+        } AS user_status,
+        (IF $sort = 'popular' THEN counter.likes + counter.replies ELSE IF $sort = 'oldest' THEN -time::nano(time.created_at) ELSE time::nano(time.created_at) END) AS rank
         OMIT time
     FROM (SELECT *, search::score(1) AS score FROM topic WHERE $query = "" OR title @1@ $query)
-    WHERE array::is_empty($tags) OR id->tag_line[WHERE meta::id(out) IN $tags]
-    ORDER BY score
-    LIMIT 20
+    WHERE (array::is_empty($tags) OR id->tag_line[WHERE meta::id(out) IN $tags])
+        AND (array::is_empty($hidden_tags) OR !(id->tag_line[WHERE meta::id(out) IN $hidden_tags]))
+    ORDER BY score DESC, rank DESC
+    LIMIT $limit
     START $offset
     FETCH counter;
     "#;
@@ -164,6 +369,79 @@ impl DBQuery {
     };
     "#;
 
+    pub const CREATE_GUEST: &'static str = r#"
+    CREATE ONLY user CONTENT {
+        email: string::concat(rand::uuid(), "@guest.invalid"),
+        password: "",
+        guest: true
+    };
+    "#;
+
+    pub const CREATE_SESSION: &'static str = r#"
+    CREATE ONLY session CONTENT {
+        user: $user,
+        refresh: crypto::sha256($refresh),
+        remember_me: $remember_me,
+        user_agent: $user_agent,
+        expires_at: $expires_at
+    };
+    "#;
+
+    pub const CREATE_ACCESS_TOKEN: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $entry = (CREATE ONLY access_token CONTENT {
+        user: $user,
+        name: $name,
+        scopes: $scopes,
+        token: crypto::sha256($access_token),
+        expires_at: $expires_at
+    });
+
+    RETURN meta::id($entry.id);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const CREATE_EMAIL_VERIFICATION: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    UPDATE email_verification SET is_used = true WHERE user = $user AND is_used = false;
+
+    CREATE ONLY email_verification CONTENT {
+        user: $user,
+        email: $email,
+        token: crypto::sha256($verification_token),
+        expires_at: $expires_at
+    };
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const CREATE_PASSWORD_RESET: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    UPDATE password_reset SET is_used = true WHERE user = $user AND is_used = false;
+
+    CREATE ONLY password_reset CONTENT {
+        user: $user,
+        token: crypto::sha256($reset_token),
+        expires_at: $expires_at
+    };
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const CREATE_OIDC_STATE: &'static str = r#"
+    CREATE ONLY oidc_state CONTENT {
+        state: crypto::sha256($state),
+        verifier: $verifier,
+        nonce: $nonce,
+        remember_me: $remember_me,
+        expires_at: $expires_at
+    };
+    "#;
+
     pub const CREATE_TOPIC: &'static str = r#"
     BEGIN TRANSACTION;
 
@@ -200,6 +478,275 @@ impl DBQuery {
     COMMIT TRANSACTION;
     "#;
 
+    pub const REFRESH_SESSION: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $user_session = (SELECT * FROM ONLY session WHERE refresh = crypto::sha256($refresh) LIMIT 1);
+
+    IF $user_session = NONE {
+        UPDATE session SET is_revoked = true WHERE previous_refresh = crypto::sha256($refresh);
+        RETURN NONE;
+    };
+
+    IF $user_session.is_revoked OR $user_session.expires_at <= time::now() {
+        RETURN NONE;
+    };
+
+    RETURN (UPDATE ONLY $user_session.id SET previous_refresh = refresh, refresh = crypto::sha256($new_refresh));
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const REVOKE_SESSION: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    IF ((SELECT * FROM ONLY $user_session WHERE user = $user AND is_revoked = false LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $user_session SET is_revoked = true;
+
+    RETURN meta::id($user_session);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const REVOKE_ALL_SESSIONS: &'static str = r#"
+    UPDATE session SET is_revoked = true WHERE user = $user AND is_revoked = false;
+    "#;
+
+    pub const REVOKE_ACCESS_TOKEN: &'static str = r#"
+    UPDATE access_token SET is_revoked = true WHERE id = $access_token AND user = $user AND is_revoked = false RETURN VALUE meta::id(id);
+    "#;
+
+    pub const REVOKE_SESSION_FROM_REFRESH: &'static str = r#"
+    UPDATE session SET is_revoked = true WHERE refresh = crypto::sha256($refresh);
+    "#;
+
+    pub const RESET_PASSWORD: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $reset = (SELECT * FROM ONLY password_reset WHERE token = crypto::sha256($reset_token) AND is_used = false AND expires_at > time::now() LIMIT 1);
+
+    IF $reset = NONE {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $reset.id SET is_used = true;
+    UPDATE ONLY $reset.user SET password = $password;
+    UPDATE session SET is_revoked = true WHERE user = $reset.user AND is_revoked = false;
+
+    RETURN meta::id($reset.user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const UPDATE_PASSWORD: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    UPDATE ONLY $user SET password = $password;
+    UPDATE session SET is_revoked = true WHERE user = $user AND id != $user_session AND is_revoked = false;
+
+    RETURN meta::id($user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const REHASH_PASSWORD: &'static str = r#"
+    UPDATE ONLY $user SET password = $password;
+    "#;
+
+    pub const VERIFY_EMAIL: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $verification = (SELECT * FROM ONLY email_verification WHERE token = crypto::sha256($verification_token) AND is_used = false AND expires_at > time::now() LIMIT 1);
+
+    IF $verification = NONE {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $verification.id SET is_used = true;
+    UPDATE ONLY $verification.user SET email = $verification.email, verified = true;
+
+    RETURN meta::id($verification.user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const RECORD_LOGIN_FAILURE: &'static str = r#"
+    UPSERT ONLY $attempt SET
+        failures = IF last_failed_at != NONE AND last_failed_at > time::now() - 1h THEN failures + 1 ELSE 1 END,
+        last_failed_at = time::now(),
+        locked_until = IF failures >= $threshold THEN
+            time::now() + duration::from::secs(<int> math::min([$max, $base * math::pow(2, failures - $threshold)]))
+        ELSE
+            NONE
+        END;
+    "#;
+
+    pub const CONSUME_OIDC_STATE: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $pending = (SELECT * FROM ONLY oidc_state WHERE state = crypto::sha256($state) AND expires_at > time::now() LIMIT 1);
+
+    IF $pending = NONE {
+        RETURN NONE;
+    };
+
+    DELETE $pending.id;
+    DELETE oidc_state WHERE expires_at <= time::now();
+
+    RETURN $pending;
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const LINK_OIDC_ACCOUNT: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $account = (SELECT * FROM ONLY oidc_account WHERE issuer = $issuer AND subject = $subject LIMIT 1);
+
+    IF $account != NONE {
+        RETURN (SELECT * FROM ONLY $account.user WHERE deleted_at = NONE);
+    };
+
+    IF $email = NONE {
+        RETURN NONE;
+    };
+
+    LET $existing = (SELECT * FROM ONLY user WHERE email = $email LIMIT 1);
+
+    IF $existing = NONE {
+        CREATE user CONTENT {
+            email: $email,
+            password: ""
+        };
+    } ELSE IF !$existing.verified {
+        -- Whoever registered an unverified address may not own it, so the
+        -- provider's owner claims the account without their credentials.
+        UPDATE ONLY $existing.id SET
+            password = "",
+            totp_enabled = false,
+            totp_secret = NONE,
+            totp_pending_secret = NONE,
+            totp_last_step = NONE;
+
+        DELETE session WHERE user = $existing.id;
+        DELETE access_token WHERE user = $existing.id;
+        DELETE recovery_code WHERE user = $existing.id;
+    };
+
+    LET $user = (SELECT VALUE id FROM ONLY user WHERE email = $email LIMIT 1);
+
+    UPDATE ONLY $user SET verified = true;
+
+    CREATE oidc_account CONTENT {
+        issuer: $issuer,
+        subject: $subject,
+        user: $user
+    };
+
+    RETURN (SELECT * FROM ONLY $user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const UPDATE_USER_SETTINGS: &'static str = r#"
+    UPSERT ONLY $settings MERGE $changes RETURN AFTER;
+    "#;
+
+    pub const MUTE_IDENTITY: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $target = (SELECT * FROM ONLY $topic->user_identity WHERE identity = $identity LIMIT 1);
+
+    IF $target = NONE OR $target.out = $user {
+        RETURN NONE;
+    };
+
+    IF $muted {
+        IF ((SELECT * FROM ONLY muted_identity WHERE user = $user AND topic = $topic AND identity = $identity LIMIT 1) = NONE) {
+            CREATE muted_identity SET user = $user, topic = $topic, identity = $identity;
+        };
+    } ELSE {
+        DELETE muted_identity WHERE user = $user AND topic = $topic AND identity = $identity;
+    };
+
+    RETURN $identity;
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const SET_PENDING_TOTP: &'static str = r#"
+    UPDATE ONLY $user SET totp_pending_secret = $secret;
+    "#;
+
+    pub const ENABLE_TOTP: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    IF ((SELECT * FROM ONLY $user WHERE totp_pending_secret = $secret LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $user SET totp_enabled = true, totp_secret = $secret, totp_pending_secret = NONE;
+
+    DELETE recovery_code WHERE user = $user;
+
+    FOR $code IN $codes {
+        CREATE recovery_code SET user = $user, code = crypto::sha256($code);
+    };
+
+    RETURN meta::id($user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const USE_TOTP_STEP: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    IF ((SELECT * FROM ONLY $user WHERE totp_last_step = NONE OR totp_last_step < $step LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $user SET totp_last_step = $step;
+
+    RETURN $user;
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const DISABLE_TOTP: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    UPDATE ONLY $user SET totp_enabled = false, totp_secret = NONE, totp_pending_secret = NONE;
+
+    DELETE recovery_code WHERE user = $user;
+
+    RETURN meta::id($user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const USE_RECOVERY_CODE: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    LET $recovery = (SELECT * FROM ONLY recovery_code WHERE user = $user AND code = crypto::sha256($code) AND is_used = false LIMIT 1);
+
+    IF $recovery = NONE {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $recovery.id SET is_used = true;
+
+    RETURN meta::id($recovery.id);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const DELETE_LOGIN_ATTEMPT: &'static str = r#"
+    DELETE $attempt;
+    "#;
+
     pub const UPDATE_TOPIC: &'static str = r#"
     BEGIN TRANSACTION;
    
@@ -207,13 +754,24 @@ impl DBQuery {
         RETURN NONE;
     };
     
-    UPDATE ONLY $topic SET content = $content, title = $title;
+    IF $topic.title != $title OR $topic.content != $content {
+        CREATE revision SET record = $topic, title = $topic.title, content = $topic.content;
+        UPDATE ONLY $topic SET content = $content, title = $title, edited_at = time::now();
+    };
      
-    RELATE $topic -> tag_line -> (
-        SELECT *
-        FROM (INSERT INTO tag $tags ON DUPLICATE KEY UPDATE indexed += 1)
-        WHERE (SELECT VALUE id FROM ONLY $topic->tag_line WHERE out = $parent.id LIMIT 1) = NONE
-    );
+    LET $current = (SELECT VALUE out FROM $topic->tag_line);
+    LET $added = array::complement($tags.id, $current);
+    LET $removed = array::complement($current, $tags.id);
+
+    IF !array::is_empty($removed) {
+        DELETE tag_line WHERE in = $topic AND out IN $removed;
+        UPDATE $removed SET indexed -= 1;
+        DELETE tag WHERE id IN $removed AND indexed <= 0;
+    };
+
+    IF !array::is_empty($added) {
+        RELATE $topic -> tag_line -> (INSERT INTO tag $tags[WHERE id IN $added] ON DUPLICATE KEY UPDATE indexed += 1);
+    };
     
     RETURN meta::id($topic.id);
     
@@ -231,13 +789,156 @@ impl DBQuery {
     	RETURN NONE;
     };
 
-    UPDATE ONLY $reply SET content = $content;
+    IF $reply.is_deleted {
+        RETURN NONE;
+    };
+
+    IF $reply.content != $content {
+        CREATE revision SET record = $reply, content = $reply.content;
+        UPDATE ONLY $reply SET content = $content, edited_at = time::now();
+    };
 
     RETURN meta::id($reply.id);
 
     COMMIT TRANSACTION;
     "#;
 
+    pub const DELETE_ACCOUNT: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    UPDATE (SELECT VALUE out.counter FROM likes WHERE in = $user AND is_deleted = false) SET likes -= 1;
+    UPDATE (SELECT VALUE out.counter FROM shares WHERE in = $user) SET shares -= 1;
+
+    DELETE likes WHERE in = $user;
+    DELETE shares WHERE in = $user;
+    DELETE session WHERE user = $user;
+    DELETE password_reset WHERE user = $user;
+    DELETE email_verification WHERE user = $user;
+    DELETE recovery_code WHERE user = $user;
+    DELETE oidc_account WHERE user = $user;
+    DELETE access_token WHERE user = $user;
+    DELETE muted_identity WHERE user = $user;
+    DELETE type::thing("user_settings", meta::id($user));
+
+    -- Hand each topic's posts to a fresh tombstone user so the identity shown
+    -- in that topic survives without linking the topics to one another.
+    FOR $identity IN (SELECT * FROM user_identity WHERE out = $user) {
+        LET $topic = $identity.in;
+        LET $posts = (SELECT VALUE out FROM wrote WHERE in = $user AND (out = $topic OR out IN $topic->contains->reply));
+        LET $tombstone = (CREATE ONLY user CONTENT {
+            email: string::concat(rand::uuid(), "@deleted.invalid"),
+            password: "",
+            deleted_at: time::now()
+        }).id;
+
+        DELETE $identity.id;
+        DELETE wrote WHERE in = $user AND out IN $posts;
+
+        FOR $post IN $posts {
+            RELATE $tombstone -> wrote -> $post;
+        };
+
+        IF ((SELECT * FROM ONLY $topic->user_identity WHERE out = $tombstone LIMIT 1) = NONE) {
+            RELATE $topic -> user_identity -> $tombstone;
+        };
+
+        -- Recreating the edges bumped the user counter; restore the original identity.
+        UPDATE user_identity SET identity = $identity.identity WHERE in = $topic AND out = $tombstone;
+        UPDATE ONLY $topic.counter SET users -= 1;
+    };
+
+    UPDATE ONLY $user SET
+        email = string::concat(meta::id($user), "@deleted.invalid"),
+        password = "",
+        verified = false,
+        totp_enabled = false,
+        totp_secret = NONE,
+        totp_pending_secret = NONE,
+        deleted_at = time::now();
+
+    RETURN meta::id($user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const DELETE_TOPIC: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    IF ((SELECT * FROM ONLY $topic LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    IF !$moderator AND ((SELECT * FROM ONLY $topic<-wrote WHERE in = $user LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    LET $replies = (SELECT VALUE out FROM $topic->contains);
+    LET $tags = (SELECT VALUE out FROM $topic->tag_line);
+
+    UPDATE $tags SET indexed -= 1;
+
+    DELETE likes, shares, wrote WHERE out = $topic OR out IN $replies;
+    DELETE contains, tag_line, user_identity WHERE in = $topic;
+    DELETE muted_identity WHERE topic = $topic;
+    DELETE revision WHERE record = $topic OR record IN $replies;
+
+    DELETE $replies;
+    DELETE $topic;
+    DELETE tag WHERE id IN $tags AND indexed <= 0;
+
+    RETURN meta::id($topic);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const DELETE_REPLY: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    IF ((SELECT * FROM ONLY $reply<-contains WHERE in = $topic LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    IF !$moderator AND ((SELECT * FROM ONLY $reply<-wrote WHERE in = $user LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    IF !$reply.is_deleted {
+        CREATE revision SET record = $reply, content = $reply.content;
+        UPDATE ONLY $reply SET content = "", is_deleted = true;
+    };
+
+    RETURN meta::id($reply);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const LOCK_TOPIC: &'static str = r#"
+    UPDATE ONLY $topic SET is_locked = $locked RETURN VALUE meta::id(id);
+    "#;
+
+    pub const BAN_USER: &'static str = r#"
+    BEGIN TRANSACTION;
+
+    IF ((SELECT * FROM ONLY $user WHERE role = 'user' OR ($role = 'admin' AND role != 'admin') LIMIT 1) = NONE) {
+        RETURN NONE;
+    };
+
+    UPDATE ONLY $user SET is_banned = $banned;
+
+    IF $banned {
+        UPDATE session SET is_revoked = true WHERE user = $user AND is_revoked = false;
+        UPDATE access_token SET is_revoked = true WHERE user = $user AND is_revoked = false;
+    };
+
+    RETURN meta::id($user);
+
+    COMMIT TRANSACTION;
+    "#;
+
+    pub const SET_ROLE: &'static str = r#"
+    UPDATE user SET role = $role WHERE email = $email AND deleted_at = NONE RETURN VALUE meta::id(id);
+    "#;
+
     pub const LIKE_POST: &'static str = r#"
     BEGIN TRANSACTION;
     
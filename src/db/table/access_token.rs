@@ -0,0 +1,68 @@
+use super::User;
+
+use async_graphql::{Enum, Object, ID};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Enum, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    #[serde(rename = "read")]
+    Read,
+    #[serde(rename = "topic:write")]
+    TopicWrite,
+    #[serde(rename = "reply:write")]
+    ReplyWrite,
+}
+
+#[derive(Deserialize)]
+pub struct AccessGrant {
+    user: User,
+    scopes: Vec<Scope>,
+}
+
+impl AccessGrant {
+    pub fn user(self) -> User {
+        self.user
+    }
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AccessToken {
+    id: ID,
+    name: String,
+    scopes: Vec<Scope>,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[Object]
+impl AccessToken {
+    async fn id(&self) -> &ID {
+        &self.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    async fn created_at(&self) -> &DateTime<Utc> {
+        &self.created_at
+    }
+
+    async fn last_used_at(&self) -> Option<&DateTime<Utc>> {
+        self.last_used_at.as_ref()
+    }
+
+    async fn expires_at(&self) -> Option<&DateTime<Utc>> {
+        self.expires_at.as_ref()
+    }
+}
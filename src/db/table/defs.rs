@@ -19,7 +19,7 @@ impl Record {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Counter {
     likes: u64,
     shares: u64,
@@ -46,7 +46,7 @@ impl Counter {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct UserStatus {
     identity: u64,
     is_liked: bool,
@@ -54,6 +54,12 @@ pub struct UserStatus {
     is_shared: bool,
 }
 
+impl UserStatus {
+    pub fn owned(&self) -> bool {
+        self.is_owner
+    }
+}
+
 #[Object]
 impl UserStatus {
     async fn identity(&self) -> u64 {
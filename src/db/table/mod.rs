@@ -1,9 +1,19 @@
+mod access_token;
 mod defs;
 mod reply;
+mod revision;
+mod session;
+mod settings;
+mod tag;
 mod topic;
 mod user;
 
+pub use access_token::{AccessGrant, AccessToken, Scope};
 pub use defs::Record;
 pub use reply::Reply;
+pub use revision::Revision;
+pub use session::{ActiveSession, Session};
+pub use settings::{TopicSort, UserSettings};
+pub use tag::TagUsage;
 pub use topic::Topic;
-pub use user::User;
+pub use user::{Profile, Role, User};
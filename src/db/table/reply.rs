@@ -1,10 +1,16 @@
 use super::defs::{Counter, UserStatus};
 
-use async_graphql::{Object, ID};
+use crate::db::defs::DBTable;
+use crate::Result;
+
+use super::Revision;
+
+use async_graphql::{Context, Object, ID};
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Reply {
     id: ID,
     content: String,
@@ -12,6 +18,11 @@ pub struct Reply {
     parent: Option<Parent>,
     activity: DateTime<Utc>,
     user_status: UserStatus,
+    #[serde(default)]
+    is_deleted: bool,
+    #[serde(default)]
+    is_muted: bool,
+    edited_at: Option<DateTime<Utc>>,
 }
 
 #[Object]
@@ -21,7 +32,10 @@ impl Reply {
     }
 
     async fn content(&self) -> &str {
-        &self.content
+        match self.is_muted {
+            true => "",
+            false => &self.content,
+        }
     }
 
     async fn counter(&self) -> &Counter {
@@ -39,9 +53,31 @@ impl Reply {
     async fn user_status(&self) -> &UserStatus {
         &self.user_status
     }
+
+    async fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+
+    async fn is_muted(&self) -> bool {
+        self.is_muted
+    }
+
+    async fn edited(&self) -> bool {
+        self.edited_at.is_some()
+    }
+
+    async fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.edited_at.as_ref()
+    }
+
+    async fn revisions(&self, ctx: &Context<'_>, offset: u64) -> Result<Vec<Revision>> {
+        let record = Thing::from((DBTable::REPLY, self.id.as_str()));
+
+        Revision::list(ctx, record, self.user_status.owned(), offset).await
+    }
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 struct Parent {
     id: ID,
     user_identity: u64,
@@ -0,0 +1,64 @@
+use crate::auth::Auth;
+use crate::db::defs::{DBQuery, SharedDB};
+use crate::{ClientError, Error, Result};
+
+use super::Role;
+
+use async_graphql::{Context, Object, ID};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+#[derive(Deserialize)]
+pub struct Revision {
+    id: ID,
+    title: Option<String>,
+    content: String,
+    created_at: DateTime<Utc>,
+}
+
+impl Revision {
+    /// Lists the previous revisions of a topic or reply, newest first. Only
+    /// the author and moderators may read them.
+    pub async fn list(
+        ctx: &Context<'_>,
+        record: Thing,
+        is_owner: bool,
+        offset: u64,
+    ) -> Result<Vec<Revision>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let user = Auth::authenticate(ctx).await.unwrap_or_default();
+
+        if !is_owner && user.role() < Role::Moderator {
+            return Err(Error::Client(ClientError::Forbidden));
+        }
+
+        let mut response = db
+            .query(DBQuery::SELECT_REVISIONS)
+            .bind(("record", record))
+            .bind(("offset", offset))
+            .await?;
+
+        Ok(response.take::<Vec<Revision>>(0)?)
+    }
+}
+
+#[Object]
+impl Revision {
+    async fn id(&self) -> &ID {
+        &self.id
+    }
+
+    async fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    async fn content(&self) -> &str {
+        &self.content
+    }
+
+    async fn created_at(&self) -> &DateTime<Utc> {
+        &self.created_at
+    }
+}
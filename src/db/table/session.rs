@@ -0,0 +1,62 @@
+use async_graphql::{Object, ID};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+#[derive(Deserialize, Clone)]
+pub struct Session {
+    id: Thing,
+    user: Thing,
+    remember_me: bool,
+}
+
+impl Session {
+    pub fn id(&self) -> &Thing {
+        &self.id
+    }
+
+    pub fn user(&self) -> &Thing {
+        &self.user
+    }
+
+    pub fn remember_me(&self) -> bool {
+        self.remember_me
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ActiveSession {
+    id: ID,
+    remember_me: bool,
+    user_agent: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+    is_current: bool,
+}
+
+#[Object]
+impl ActiveSession {
+    async fn id(&self) -> &ID {
+        &self.id
+    }
+
+    async fn remember_me(&self) -> bool {
+        self.remember_me
+    }
+
+    async fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    async fn created_at(&self) -> &DateTime<Utc> {
+        &self.created_at
+    }
+
+    async fn last_seen_at(&self) -> &DateTime<Utc> {
+        &self.last_seen_at
+    }
+
+    async fn is_current(&self) -> bool {
+        self.is_current
+    }
+}
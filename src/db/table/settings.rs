@@ -0,0 +1,100 @@
+use async_graphql::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+#[derive(Enum, Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TopicSort {
+    #[default]
+    Newest,
+    Oldest,
+    Popular,
+}
+
+/// Preferences the topic queries honour. The notification toggles are the
+/// exception: the server sends no notifications and only stores them so that
+/// clients share them across devices.
+#[derive(Deserialize)]
+pub struct UserSettings {
+    #[serde(default)]
+    sort: TopicSort,
+    #[serde(default = "UserSettings::default_page_size")]
+    page_size: u64,
+    #[serde(default)]
+    muted_tags: Vec<String>,
+    #[serde(default)]
+    hide_nsfw: bool,
+    #[serde(default = "UserSettings::default_notify")]
+    notify_replies: bool,
+    #[serde(default = "UserSettings::default_notify")]
+    notify_likes: bool,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            sort: TopicSort::default(),
+            page_size: Self::default_page_size(),
+            muted_tags: Vec::new(),
+            hide_nsfw: false,
+            notify_replies: Self::default_notify(),
+            notify_likes: Self::default_notify(),
+        }
+    }
+}
+
+impl UserSettings {
+    pub const NSFW_TAG: &'static str = "nsfw";
+
+    fn default_page_size() -> u64 {
+        20
+    }
+
+    fn default_notify() -> bool {
+        true
+    }
+
+    pub fn topic_sort(&self) -> TopicSort {
+        self.sort
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.page_size
+    }
+
+    pub fn hidden_tags(&self) -> Vec<String> {
+        let mut tags = self.muted_tags.clone();
+
+        if self.hide_nsfw && !tags.iter().any(|tag| tag == Self::NSFW_TAG) {
+            tags.push(Self::NSFW_TAG.to_string());
+        }
+
+        tags
+    }
+}
+
+#[Object]
+impl UserSettings {
+    async fn sort(&self) -> TopicSort {
+        self.sort
+    }
+
+    async fn page_size(&self) -> u64 {
+        self.page_size
+    }
+
+    async fn muted_tags(&self) -> Vec<&str> {
+        self.muted_tags.iter().map(|tag| tag.as_str()).collect()
+    }
+
+    async fn hide_nsfw(&self) -> bool {
+        self.hide_nsfw
+    }
+
+    async fn notify_replies(&self) -> bool {
+        self.notify_replies
+    }
+
+    async fn notify_likes(&self) -> bool {
+        self.notify_likes
+    }
+}
@@ -0,0 +1,29 @@
+use async_graphql::Object;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct TagUsage {
+    name: String,
+    indexed: u64,
+    recent: Option<u64>,
+    growth: Option<i64>,
+}
+
+#[Object]
+impl TagUsage {
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn indexed(&self) -> u64 {
+        self.indexed
+    }
+
+    async fn recent(&self) -> Option<u64> {
+        self.recent
+    }
+
+    async fn growth(&self) -> Option<i64> {
+        self.growth
+    }
+}
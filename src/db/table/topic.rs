@@ -2,11 +2,17 @@ use crate::db::table::defs::Counter;
 
 use super::defs::UserStatus;
 
-use async_graphql::{Object, ID};
+use crate::db::defs::DBTable;
+use crate::Result;
+
+use super::Revision;
+
+use async_graphql::{Context, Object, ID};
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Topic {
     id: ID,
     title: String,
@@ -15,6 +21,9 @@ pub struct Topic {
     counter: Counter,
     activity: DateTime<Utc>,
     user_status: UserStatus,
+    #[serde(default)]
+    is_locked: bool,
+    edited_at: Option<DateTime<Utc>>,
 }
 
 #[Object]
@@ -46,4 +55,22 @@ impl Topic {
     async fn user_status(&self) -> &UserStatus {
         &self.user_status
     }
+
+    async fn is_locked(&self) -> bool {
+        self.is_locked
+    }
+
+    async fn edited(&self) -> bool {
+        self.edited_at.is_some()
+    }
+
+    async fn updated_at(&self) -> Option<&DateTime<Utc>> {
+        self.edited_at.as_ref()
+    }
+
+    async fn revisions(&self, ctx: &Context<'_>, offset: u64) -> Result<Vec<Revision>> {
+        let record = Thing::from((DBTable::TOPIC, self.id.as_str()));
+
+        Revision::list(ctx, record, self.user_status.owned(), offset).await
+    }
 }
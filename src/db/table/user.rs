@@ -1,17 +1,51 @@
-use serde::Deserialize;
+use async_graphql::{Enum, Object, ID};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
+#[derive(
+    Enum, Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct User {
     id: Thing,
+    email: String,
     password: String,
+    #[serde(default)]
+    verified: bool,
+    #[serde(default)]
+    guest: bool,
+    #[serde(default)]
+    role: Role,
+    #[serde(default)]
+    is_banned: bool,
+    #[serde(default)]
+    totp_enabled: bool,
+    totp_secret: Option<String>,
+    totp_pending_secret: Option<String>,
 }
 
 impl Default for User {
     fn default() -> Self {
         Self {
             id: Thing::from(("0", "0")),
+            email: String::default(),
             password: String::default(),
+            verified: false,
+            guest: false,
+            role: Role::User,
+            is_banned: false,
+            totp_enabled: false,
+            totp_secret: None,
+            totp_pending_secret: None,
         }
     }
 }
@@ -21,7 +55,106 @@ impl User {
         &self.id
     }
 
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
     pub fn password(&self) -> &str {
         &self.password
     }
+
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+
+    pub fn is_guest(&self) -> bool {
+        self.guest
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn is_banned(&self) -> bool {
+        self.is_banned
+    }
+
+    pub fn is_totp_enabled(&self) -> bool {
+        self.totp_enabled
+    }
+
+    pub fn totp_secret(&self) -> Option<&str> {
+        self.totp_secret.as_deref()
+    }
+
+    pub fn totp_pending_secret(&self) -> Option<&str> {
+        self.totp_pending_secret.as_deref()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Profile {
+    id: ID,
+    email: String,
+    #[serde(default)]
+    verified: bool,
+    #[serde(default)]
+    guest: bool,
+    #[serde(default)]
+    role: Role,
+    created_at: DateTime<Utc>,
+    stats: ProfileStats,
+}
+
+#[Object]
+impl Profile {
+    async fn id(&self) -> &ID {
+        &self.id
+    }
+
+    async fn email(&self) -> &str {
+        &self.email
+    }
+
+    async fn is_verified(&self) -> bool {
+        self.verified
+    }
+
+    async fn is_guest(&self) -> bool {
+        self.guest
+    }
+
+    async fn role(&self) -> Role {
+        self.role
+    }
+
+    async fn created_at(&self) -> &DateTime<Utc> {
+        &self.created_at
+    }
+
+    async fn stats(&self) -> &ProfileStats {
+        &self.stats
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ProfileStats {
+    topics: u64,
+    replies: u64,
+    likes_received: u64,
+}
+
+#[Object]
+impl ProfileStats {
+    async fn topics(&self) -> u64 {
+        self.topics
+    }
+
+    async fn replies(&self) -> u64 {
+        self.replies
+    }
+
+    async fn likes_received(&self) -> u64 {
+        self.likes_received
+    }
 }
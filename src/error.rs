@@ -1,5 +1,6 @@
 use async_graphql::ErrorExtensions;
 use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -13,15 +14,33 @@ pub enum ClientError {
     // Login Errors
     EmailNotFound,
     InvalidPassword,
+    InvalidCredentials,
+    TooManyAttempts,
 
     // Topic Errors
     TopicNotFound,
+    TopicLocked,
 
     // Reply Errors
     ReplyNotFound,
 
     // Auth Errors
     Unauthorized,
+    InvalidToken,
+    InvalidCsrfToken,
+    EmailNotVerified,
+    Forbidden,
+    Banned,
+    ReauthenticationRequired,
+
+    // Two-Factor Errors
+    InvalidCode,
+
+    // Session Errors
+    SessionNotFound,
+
+    // Access Token Errors
+    AccessTokenNotFound,
 }
 
 impl From<ClientError> for String {
@@ -38,6 +57,18 @@ impl From<ClientError> for String {
             ClientError::ReplyNotFound => "REPLY_NOT_FOUND".into(),
             ClientError::EmailNotFound => "EMAIL_NOT_FOUND".into(),
             ClientError::InvalidPassword => "INVALID_PASSWORD".into(),
+            ClientError::InvalidCredentials => "INVALID_CREDENTIALS".into(),
+            ClientError::TooManyAttempts => "TOO_MANY_ATTEMPTS".into(),
+            ClientError::SessionNotFound => "SESSION_NOT_FOUND".into(),
+            ClientError::InvalidToken => "INVALID_TOKEN".into(),
+            ClientError::EmailNotVerified => "EMAIL_NOT_VERIFIED".into(),
+            ClientError::InvalidCode => "INVALID_CODE".into(),
+            ClientError::Forbidden => "FORBIDDEN".into(),
+            ClientError::Banned => "BANNED".into(),
+            ClientError::TopicLocked => "TOPIC_LOCKED".into(),
+            ClientError::AccessTokenNotFound => "ACCESS_TOKEN_NOT_FOUND".into(),
+            ClientError::InvalidCsrfToken => "INVALID_CSRF_TOKEN".into(),
+            ClientError::ReauthenticationRequired => "REAUTHENTICATION_REQUIRED".into(),
         }
     }
 }
@@ -52,6 +83,18 @@ impl From<&ClientError> for StatusCode {
             ClientError::Unauthorized => StatusCode::UNAUTHORIZED,
             ClientError::BadRequest(_) => StatusCode::BAD_REQUEST,
             ClientError::InvalidPassword => StatusCode::BAD_REQUEST,
+            ClientError::InvalidCredentials => StatusCode::BAD_REQUEST,
+            ClientError::TooManyAttempts => StatusCode::TOO_MANY_REQUESTS,
+            ClientError::SessionNotFound => StatusCode::NOT_FOUND,
+            ClientError::InvalidToken => StatusCode::BAD_REQUEST,
+            ClientError::EmailNotVerified => StatusCode::FORBIDDEN,
+            ClientError::InvalidCode => StatusCode::BAD_REQUEST,
+            ClientError::Forbidden => StatusCode::FORBIDDEN,
+            ClientError::Banned => StatusCode::FORBIDDEN,
+            ClientError::TopicLocked => StatusCode::FORBIDDEN,
+            ClientError::AccessTokenNotFound => StatusCode::NOT_FOUND,
+            ClientError::InvalidCsrfToken => StatusCode::FORBIDDEN,
+            ClientError::ReauthenticationRequired => StatusCode::FORBIDDEN,
         }
     }
 }
@@ -61,19 +104,40 @@ pub enum Error {
     // Common Errors
     #[from]
     Io(std::io::Error),
-    #[from]
-    SurrealDB(surrealdb::Error),
+    SurrealDB(Box<surrealdb::Error>),
     #[from]
     Bcrypt(bcrypt::BcryptError),
     #[from]
+    Argon2(argon2::Error),
+    #[from]
+    PasswordHash(argon2::password_hash::Error),
+    #[from]
     AsyncGraphql(async_graphql::Error),
     #[from]
     JsonWebToken(jsonwebtoken::errors::Error),
     #[from]
     InvalidHeaderValue(axum::http::header::InvalidHeaderValue),
+    #[from]
+    Smtp(lettre::transport::smtp::Error),
+    #[from]
+    Email(lettre::error::Error),
+    #[from]
+    Address(lettre::address::AddressError),
+    #[from]
+    SerdeJson(serde_json::Error),
+    #[from]
+    Reqwest(reqwest::Error),
+    #[from]
+    Totp(totp_rs::TotpUrlError),
+    #[from]
+    TotpSecret(totp_rs::SecretParseError),
+    #[from]
+    SystemTime(std::time::SystemTimeError),
 
     // Unique Errors
     MissingEnv(String),
+    InvalidEnv(String),
+    InvalidKey(String),
     RecordNotCreated(String),
 
     // Client Errors
@@ -81,6 +145,12 @@ pub enum Error {
     Client(ClientError),
 }
 
+impl From<surrealdb::Error> for Error {
+    fn from(val: surrealdb::Error) -> Self {
+        Self::SurrealDB(Box::new(val))
+    }
+}
+
 impl From<Error> for String {
     fn from(val: Error) -> Self {
         match val {
@@ -107,14 +177,39 @@ impl From<Error> for async_graphql::Error {
             Error::Client(client_error) => tracing::debug!("ClientError::{client_error:?}"),
             Error::Io(e) => tracing::error!("Error::Io: {e}"),
             Error::Bcrypt(e) => tracing::error!("Error::Bcrypt: {e}"),
+            Error::Argon2(e) => tracing::error!("Error::Argon2: {e}"),
+            Error::PasswordHash(e) => tracing::error!("Error::PasswordHash: {e}"),
             Error::SurrealDB(e) => tracing::error!("Error::SurrealDB: {e}"),
             Error::MissingEnv(e) => tracing::error!("Error::MisingEnv: {e}"),
+            Error::InvalidEnv(e) => tracing::error!("Error::InvalidEnv: {e}"),
             Error::JsonWebToken(e) => tracing::error!("Error::JsonWebToken: {e}"),
             Error::AsyncGraphql(e) => tracing::error!("Error::AsyncGraphql: {e:#?}"),
             Error::RecordNotCreated(e) => tracing::error!("Error::RecordNotCreated: {e}"),
             Error::InvalidHeaderValue(e) => tracing::error!("Error::InvalidHeaderValue: {e}"),
+            Error::Smtp(e) => tracing::error!("Error::Smtp: {e}"),
+            Error::Email(e) => tracing::error!("Error::Email: {e}"),
+            Error::Address(e) => tracing::error!("Error::Address: {e}"),
+            Error::SerdeJson(e) => tracing::error!("Error::SerdeJson: {e}"),
+            Error::InvalidKey(e) => tracing::error!("Error::InvalidKey: {e}"),
+            Error::Reqwest(e) => tracing::error!("Error::Reqwest: {e}"),
+            Error::Totp(e) => tracing::error!("Error::Totp: {e}"),
+            Error::TotpSecret(e) => tracing::error!("Error::TotpSecret: {e}"),
+            Error::SystemTime(e) => tracing::error!("Error::SystemTime: {e}"),
         }
 
         async_graphql::Error::new(val).extend_with(|_, e| e.set("code", code.as_u16()))
     }
 }
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let code: StatusCode = (&self).into();
+
+        match &self {
+            Error::Client(client_error) => tracing::debug!("ClientError::{client_error:?}"),
+            _ => tracing::error!("{self:?}"),
+        }
+
+        (code, String::from(self)).into_response()
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     db::{
         defs::{DBQuery, DBTable, SharedDB},
-        table::Record,
+        table::{Record, UserSettings},
     },
     ClientError, Error, Result,
 };
@@ -70,3 +70,37 @@ pub async fn validate_topic(db: &SharedDB, id: &str) -> Result<Record> {
 
     future.instrument(span).await
 }
+
+pub async fn ensure_unlocked(db: &SharedDB, topic: &Thing) -> Result<()> {
+    let mut response = db
+        .query(DBQuery::SELECT_TOPIC_LOCK)
+        .bind(("topic", topic.to_owned()))
+        .await?;
+
+    if response.take::<Option<bool>>(0)?.unwrap_or(false) {
+        // Temporary
+        tracing::debug!("Topic locked");
+
+        return Err(Error::Client(ClientError::TopicLocked));
+    }
+
+    Ok(())
+}
+
+pub async fn load_settings(db: &SharedDB, user: &Thing) -> Result<UserSettings> {
+    if user.tb != DBTable::USER {
+        return Ok(UserSettings::default());
+    }
+
+    let mut response = db
+        .query(DBQuery::SELECT_USER_SETTINGS)
+        .bind((
+            "settings",
+            Thing::from((DBTable::USER_SETTINGS, user.id.to_raw().as_str())),
+        ))
+        .await?;
+
+    Ok(response
+        .take::<Option<UserSettings>>(0)?
+        .unwrap_or_default())
+}
@@ -0,0 +1,46 @@
+use crate::auth::Auth;
+use crate::db::table::{Role, User};
+use crate::{ClientError, Error, Result};
+
+use async_graphql::{Context, Guard};
+use std::sync::OnceLock;
+
+/// Request-scoped slot for the account a guard resolved, so guarded resolvers
+/// don't authenticate the request a second time.
+#[derive(Default)]
+pub struct GuardedUser(OnceLock<User>);
+
+pub struct RoleGuard {
+    role: Role,
+}
+
+impl RoleGuard {
+    pub fn new(role: Role) -> Self {
+        Self { role }
+    }
+
+    pub fn user(ctx: &Context<'_>) -> Result<User> {
+        ctx.data::<GuardedUser>()?
+            .0
+            .get()
+            .cloned()
+            .ok_or(Error::Client(ClientError::Unauthorized))
+    }
+}
+
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        let user = Auth::authenticate_account(ctx).await?;
+
+        if user.role() < self.role {
+            // Temporary
+            tracing::debug!(role = ?user.role(), required = ?self.role, "Insufficient role");
+
+            return Err(Error::Client(ClientError::Forbidden).into());
+        }
+
+        let _ = ctx.data::<GuardedUser>()?.0.set(user);
+
+        Ok(())
+    }
+}
@@ -1,49 +1,88 @@
 mod defs;
+mod guard;
 mod mutation;
 mod query;
 
+pub use guard::GuardedUser;
 pub use mutation::RootMutation;
 pub use query::RootQuery;
 
+use crate::config;
+use crate::csrf::Csrf;
 use crate::db::defs::SharedDB;
+use crate::mailer::defs::SharedMailer;
 use crate::sse::defs::{SharedReplyChannels, SharedTopicTX};
+use crate::throttle::ClientIp;
 
+use async_graphql::Pos;
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
-use axum::extract::Request;
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{Extensions, HeaderMap};
 use axum::routing::post;
 use axum::{body::Body, Extension, Router};
 use axum_extra::headers::authorization::Bearer;
-use axum_extra::headers::Authorization;
+use axum_extra::headers::{Authorization, UserAgent};
 use axum_extra::TypedHeader;
 use defs::ApiSchema;
+use std::net::SocketAddr;
 use tower::ServiceBuilder;
 use tower_cookies::{CookieManagerLayer, Cookies};
 use tower_http::trace::TraceLayer;
 
 pub async fn handler(
+    headers: HeaderMap,
+    extensions: Extensions,
     cookies: Cookies,
     schema: Extension<ApiSchema>,
     auth_header: Option<TypedHeader<Authorization<Bearer>>>,
+    user_agent: Option<TypedHeader<UserAgent>>,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
     let mut req = req.into_inner();
 
+    if let Err(e) = Csrf::verify(&req, &headers, &cookies, auth_header.is_some()) {
+        let error: async_graphql::Error = e.into();
+
+        return async_graphql::Response::from_errors(vec![error.into_server_error(Pos::default())])
+            .into();
+    }
+
     req = req.data(cookies);
     req = req.data(auth_header);
+    req = req.data(user_agent);
+    req = req.data(GuardedUser::default());
+    req = req.data(ClientIp::resolve(
+        &headers,
+        extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr),
+        config().TRUST_PROXY,
+    ));
 
     schema.execute(req).await.into()
 }
 
-pub fn router(
+pub fn schema(
     db: &SharedDB,
+    mailer: &SharedMailer,
     topic_tx: &SharedTopicTX,
     reply_channels: &SharedReplyChannels,
-) -> Router {
-    let schema = ApiSchema::build(Default::default(), Default::default(), Default::default())
+) -> ApiSchema {
+    ApiSchema::build(Default::default(), Default::default(), Default::default())
         .data(db.clone())
+        .data(mailer.clone())
         .data(topic_tx.clone())
         .data(reply_channels.clone())
-        .finish();
+        .finish()
+}
+
+pub fn router(
+    db: &SharedDB,
+    mailer: &SharedMailer,
+    topic_tx: &SharedTopicTX,
+    reply_channels: &SharedReplyChannels,
+) -> Router {
+    let schema = schema(db, mailer, topic_tx, reply_channels);
 
     Router::new().route("/", post(handler)).layer(
         ServiceBuilder::new()
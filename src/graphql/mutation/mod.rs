@@ -1,3 +1,4 @@
+mod moderation;
 mod reply;
 mod topic;
 mod user;
@@ -20,4 +21,8 @@ impl RootMutation {
     async fn reply(&self) -> reply::ReplyMutation {
         Default::default()
     }
+
+    async fn moderation(&self) -> moderation::ModerationMutation {
+        Default::default()
+    }
 }
@@ -0,0 +1,158 @@
+use crate::db::defs::{DBQuery, SharedDB};
+use crate::db::table::Role;
+use crate::graphql::defs::validate_topic;
+use crate::graphql::guard::RoleGuard;
+use crate::sse::defs::{ReplyData, SharedReplyChannels};
+use crate::{ClientError, Error, Result};
+
+use async_graphql::{Context, InputObject, Object, ID};
+use surrealdb::sql::Thing;
+use tracing::Instrument;
+
+#[derive(InputObject, Clone)]
+struct LockTopicInput {
+    id: ID,
+    locked: bool,
+}
+
+#[derive(InputObject, Clone)]
+struct BanUserInput {
+    topic: ID,
+    identity: u64,
+    banned: bool,
+}
+
+#[derive(InputObject, Clone)]
+struct SetRoleInput {
+    #[graphql(validator(email))]
+    email: String,
+
+    role: Role,
+}
+
+#[derive(Default)]
+pub struct ModerationMutation;
+
+#[Object]
+impl ModerationMutation {
+    #[graphql(guard = "RoleGuard::new(Role::Moderator)")]
+    async fn lock_topic(&self, ctx: &Context<'_>, input: LockTopicInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+        let channels = ctx.data::<SharedReplyChannels>()?;
+
+        let input_clone = input.clone();
+
+        let future = async {
+            // Temporary
+            tracing::debug!(locked = %input.locked, "Locking topic");
+
+            let topic = validate_topic(db, &input.id).await?;
+
+            db.query(DBQuery::LOCK_TOPIC)
+                .bind(("topic", topic.id().to_owned()))
+                .bind(("locked", input.locked))
+                .await?
+                .check()?;
+
+            let channels = channels.lock().await;
+
+            if let Some(tx) = channels.get(input.id.as_str()) {
+                let _ = tx.send(ReplyData::new(input.id.clone(), "Updated", "Topic"));
+            }
+
+            match input.locked {
+                true => Ok("Topic locked successfully"),
+                false => Ok("Topic unlocked successfully"),
+            }
+        };
+
+        let span = tracing::debug_span!("Moderation", topic = %input_clone.id.as_str());
+
+        future.instrument(span).await
+    }
+
+    #[graphql(guard = "RoleGuard::new(Role::Moderator)")]
+    async fn ban_user(&self, ctx: &Context<'_>, input: BanUserInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let input_clone = input.clone();
+
+        let future = async {
+            let moderator = RoleGuard::user(ctx)?;
+            let topic = validate_topic(db, &input.topic).await?;
+
+            // Temporary
+            tracing::debug!(banned = %input.banned, "Banning user");
+
+            let mut response = db
+                .query(DBQuery::SELECT_USER_FROM_IDENTITY)
+                .bind(("topic", topic.id().to_owned()))
+                .bind(("identity", input.identity))
+                .await?;
+
+            let Some(user) = response.take::<Option<Thing>>(0)? else {
+                // Temporary
+                tracing::debug!("Identity not found");
+
+                return Err(Error::Client(ClientError::BadRequest(
+                    "Identity not found".to_string(),
+                )));
+            };
+
+            let mut response = db
+                .query(DBQuery::BAN_USER)
+                .bind(("user", user))
+                .bind(("role", moderator.role()))
+                .bind(("banned", input.banned))
+                .await?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                // Temporary
+                tracing::debug!("User outranks moderator");
+
+                return Err(Error::Client(ClientError::Forbidden));
+            };
+
+            match input.banned {
+                true => Ok("User banned successfully"),
+                false => Ok("User unbanned successfully"),
+            }
+        };
+
+        let span = tracing::debug_span!(
+            "Moderation",
+            topic = %input_clone.topic.as_str(),
+            identity = %input_clone.identity
+        );
+
+        future.instrument(span).await
+    }
+
+    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    async fn set_role(&self, ctx: &Context<'_>, input: SetRoleInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let input_clone = input.clone();
+
+        let future = async {
+            // Temporary
+            tracing::debug!(role = ?input.role, "Setting role");
+
+            let mut response = db
+                .query(DBQuery::SET_ROLE)
+                .bind(("email", input.email.to_lowercase()))
+                .bind(("role", input.role))
+                .await?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                return Err(Error::Client(ClientError::EmailNotFound));
+            };
+
+            Ok("Role updated successfully")
+        };
+
+        let span = tracing::debug_span!("Moderation", email = %input_clone.email);
+
+        future.instrument(span).await
+    }
+}
@@ -1,7 +1,7 @@
 use crate::auth::Auth;
 use crate::db::defs::{DBQuery, DBTable, SharedDB};
-use crate::db::table::Record;
-use crate::graphql::defs::validate_topic;
+use crate::db::table::{Record, Role, Scope};
+use crate::graphql::defs::{ensure_unlocked, validate_topic};
 use crate::sse::defs::{ReplyData, SharedReplyChannels};
 use crate::{ClientError, Error, Result};
 
@@ -23,6 +23,12 @@ struct UpdateReplyInput {
     content: String,
 }
 
+#[derive(InputObject, Clone)]
+struct DeleteReplyInput {
+    topic: ID,
+    reply: ID,
+}
+
 #[derive(Default)]
 pub struct ReplyMutation;
 
@@ -35,9 +41,16 @@ impl ReplyMutation {
         let input_clone = input.clone();
 
         let future = async move {
-            let user = Auth::authenticate(ctx).in_current_span().await?;
+            let user = Auth::authorize(ctx, Scope::ReplyWrite)
+                .in_current_span()
+                .await?;
+
+            Auth::ensure_verified(&user)?;
+
             let topic = validate_topic(db, &input.topic).await?;
 
+            ensure_unlocked(db, topic.id()).await?;
+
             let parent = match input.parent {
                 Some(parent) => Some(Self::validate_reply(db, &parent).await?),
                 None => None,
@@ -97,7 +110,11 @@ impl ReplyMutation {
         let input_clone = input.clone();
 
         let future = async move {
-            let user = Auth::authenticate(ctx).in_current_span().await?;
+            let user = Auth::authorize(ctx, Scope::ReplyWrite)
+                .in_current_span()
+                .await?;
+
+            ensure_unlocked(db, &Thing::from((DBTable::TOPIC, input.topic.as_str()))).await?;
 
             let future = async {
                 // Temporary
@@ -107,7 +124,8 @@ impl ReplyMutation {
                     .query(DBQuery::UPDATE_REPLY)
                     .bind(("content", input.content))
                     .bind(("user", user.id().to_owned()))
-                    .bind(("reply", input.reply.to_owned()))
+                    .bind(("topic", Thing::from((DBTable::TOPIC, input.topic.as_str()))))
+                    .bind(("reply", Thing::from((DBTable::REPLY, input.reply.as_str()))))
                     .await?;
 
                 let Some(id) = response.take::<Option<ID>>(0)? else {
@@ -143,11 +161,71 @@ impl ReplyMutation {
         future.instrument(span).await
     }
 
+    async fn delete(&self, ctx: &Context<'_>, input: DeleteReplyInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+        let channels = ctx.data::<SharedReplyChannels>()?;
+
+        let input_clone = input.clone();
+
+        let future = async move {
+            let user = Auth::authorize(ctx, Scope::ReplyWrite)
+                .in_current_span()
+                .await?;
+
+            let topic = validate_topic(db, &input.topic).await?;
+
+            let future = async {
+                // Temporary
+                tracing::debug!("Deleting reply");
+
+                let mut response = db
+                    .query(DBQuery::DELETE_REPLY)
+                    .bind(("user", user.id().to_owned()))
+                    .bind(("topic", topic.id().to_owned()))
+                    .bind(("reply", Thing::from((DBTable::REPLY, input.reply.as_str()))))
+                    .bind(("moderator", user.role() >= Role::Moderator))
+                    .await?;
+
+                let Some(id) = response.take::<Option<ID>>(0)? else {
+                    // Temporary
+                    tracing::debug!("Reply not deleted");
+
+                    return Err(Error::Client(ClientError::Unauthorized));
+                };
+
+                // Temporary
+                tracing::debug!(id = %id.as_str(), "Reply deleted");
+                tracing::debug!(
+                    path = format!("/sse/topic/{}", input.topic.as_str()),
+                    "Sending to subscribers"
+                );
+
+                let channels = channels.lock().await;
+
+                if let Some(tx) = channels.get(input.topic.as_str()) {
+                    let _ = tx.send(ReplyData::new(id.clone(), "Deleted", "Reply"));
+                }
+
+                Ok("Reply deleted successfully")
+            };
+
+            let span = tracing::debug_span!("Delete", user = %user.id().id.to_raw());
+
+            future.instrument(span).await
+        };
+
+        let span = tracing::debug_span!("Reply", topic = %input_clone.topic.as_str());
+
+        future.instrument(span).await
+    }
+
     async fn like(&self, ctx: &Context<'_>, id: ID) -> Result<&str> {
         let db = ctx.data::<SharedDB>()?;
 
         let future = async {
-            let user = Auth::authenticate(ctx).in_current_span().await?;
+            let user = Auth::authorize(ctx, Scope::ReplyWrite)
+                .in_current_span()
+                .await?;
 
             let future = async {
                 // Temporary
@@ -196,7 +274,9 @@ impl ReplyMutation {
         let db = ctx.data::<SharedDB>()?;
 
         let future = async {
-            let user = Auth::authenticate(ctx).in_current_span().await?;
+            let user = Auth::authorize(ctx, Scope::ReplyWrite)
+                .in_current_span()
+                .await?;
 
             let future = async {
                 // Temporary
@@ -1,6 +1,6 @@
 use crate::db::defs::{DBQuery, DBTable};
-use crate::db::table::Record;
-use crate::graphql::defs::validate_topic;
+use crate::db::table::{Record, Role, Scope};
+use crate::graphql::defs::{ensure_unlocked, validate_topic};
 use crate::sse::defs::{ReplyData, SharedReplyChannels, SharedTopicTX, TopicData};
 use crate::{auth::Auth, db::defs::SharedDB};
 use crate::{ClientError, Error, Result};
@@ -25,6 +25,13 @@ struct UpdateTopicInput {
     content: String,
 }
 
+#[derive(InputObject, Clone)]
+struct MuteIdentityInput {
+    topic: ID,
+    identity: u64,
+    muted: bool,
+}
+
 #[derive(Default)]
 pub struct TopicMutation;
 
@@ -35,10 +42,11 @@ impl TopicMutation {
         let tx = ctx.data::<SharedTopicTX>()?;
 
         let future = async {
-            let user = Auth::authenticate(ctx)
+            let user = Auth::authorize(ctx, Scope::TopicWrite)
                 .in_current_span()
-                .await
-                .unwrap_or_default();
+                .await?;
+
+            Auth::ensure_verified(&user)?;
 
             let user_clone = user.clone();
 
@@ -76,7 +84,7 @@ impl TopicMutation {
                 tracing::debug!(id = %id.as_str(), "Topic created");
                 tracing::debug!(path = "/sse/topic", "Sending to subscribers");
 
-                let _ = tx.send(TopicData::new(id.clone()));
+                let _ = tx.send(TopicData::new(id.clone(), "Created"));
 
                 Ok(id)
             };
@@ -96,9 +104,13 @@ impl TopicMutation {
         let channels = ctx.data::<SharedReplyChannels>()?;
 
         let future = async {
-            let user = Auth::authenticate(ctx).in_current_span().await?;
+            let user = Auth::authorize(ctx, Scope::TopicWrite)
+                .in_current_span()
+                .await?;
             let topic = validate_topic(db, &input.id).await?;
 
+            ensure_unlocked(db, topic.id()).await?;
+
             let future = async {
                 // Temporary
                 tracing::debug!("Updating topic");
@@ -160,7 +172,9 @@ impl TopicMutation {
         let db = ctx.data::<SharedDB>()?;
 
         let future = async {
-            let user = Auth::authenticate(ctx).in_current_span().await?;
+            let user = Auth::authorize(ctx, Scope::TopicWrite)
+                .in_current_span()
+                .await?;
 
             let future = async {
                 // Temporary
@@ -209,7 +223,9 @@ impl TopicMutation {
         let db = ctx.data::<SharedDB>()?;
 
         let future = async {
-            let user = Auth::authenticate(ctx).in_current_span().await?;
+            let user = Auth::authorize(ctx, Scope::TopicWrite)
+                .in_current_span()
+                .await?;
 
             let future = async {
                 // Temporary
@@ -253,4 +269,172 @@ impl TopicMutation {
 
         future.instrument(span).await
     }
+
+    async fn delete(&self, ctx: &Context<'_>, id: ID) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+        let tx = ctx.data::<SharedTopicTX>()?;
+        let channels = ctx.data::<SharedReplyChannels>()?;
+
+        let future = async {
+            let user = Auth::authorize(ctx, Scope::TopicWrite)
+                .in_current_span()
+                .await?;
+            let topic = validate_topic(db, &id).await?;
+
+            let future = async {
+                // Temporary
+                tracing::debug!("Deleting topic");
+
+                let mut response = db
+                    .query(DBQuery::DELETE_TOPIC)
+                    .bind(("user", user.id().to_owned()))
+                    .bind(("topic", topic.id().to_owned()))
+                    .bind(("moderator", user.role() >= Role::Moderator))
+                    .await?;
+
+                let Some(_) = response.take::<Option<ID>>(0)? else {
+                    // Temporary
+                    tracing::debug!("Topic not owned");
+
+                    return Err(Error::Client(ClientError::Unauthorized));
+                };
+
+                // Temporary
+                tracing::debug!("Topic deleted");
+                tracing::debug!(path = "/sse/topic", "Sending to subscribers");
+
+                let _ = tx.send(TopicData::new(id.clone(), "Deleted"));
+
+                // Temporary
+                tracing::debug!(
+                    path = format!("/sse/topic/{}", id.as_str()),
+                    "Sending to subscribers"
+                );
+
+                let channels = channels.lock().await;
+
+                if let Some(tx) = channels.get(id.as_str()) {
+                    let _ = tx.send(ReplyData::new(id.clone(), "Deleted", "Topic"));
+                }
+
+                Ok("Topic deleted successfully")
+            };
+
+            let span = tracing::debug_span!("Delete", user = %user.id().id.to_raw());
+
+            future.instrument(span).await
+        };
+
+        let span = tracing::debug_span!("Topic", id = %id.as_str());
+
+        future.instrument(span).await
+    }
+
+    /// Mutes or unmutes an identity within a topic for the caller.
+    async fn mute_identity(&self, ctx: &Context<'_>, input: MuteIdentityInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let input_clone = input.clone();
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+            let topic = validate_topic(db, &input.topic).await?;
+
+            // Temporary
+            tracing::debug!(muted = %input.muted, "Muting identity");
+
+            let mut response = db
+                .query(DBQuery::MUTE_IDENTITY)
+                .bind(("user", user.id().to_owned()))
+                .bind(("topic", topic.id().to_owned()))
+                .bind(("identity", input.identity))
+                .bind(("muted", input.muted))
+                .await?;
+
+            let Some(_) = response.take::<Option<u64>>(0)? else {
+                // Temporary
+                tracing::debug!("Identity not found");
+
+                return Err(Error::Client(ClientError::BadRequest(
+                    "Identity not found".to_string(),
+                )));
+            };
+
+            match input.muted {
+                true => Ok("Identity muted successfully"),
+                false => Ok("Identity unmuted successfully"),
+            }
+        };
+
+        let span = tracing::debug_span!(
+            "MuteIdentity",
+            topic = %input_clone.topic.as_str(),
+            identity = %input_clone.identity
+        );
+
+        future.instrument(span).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::defs::DBQuery;
+    use crate::db::table::Scope;
+    use crate::mailer::{defs::SharedMailer, file::FileMailer};
+    use crate::test_utils;
+
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn access_tokens_are_limited_to_their_scopes() {
+        let db = test_utils::db().await;
+
+        let mailer: SharedMailer = Arc::new(FileMailer::new(None));
+        let schema = test_utils::schema(&db, &mailer);
+
+        let user = test_utils::user(&db, "").await;
+
+        for (token, scope) in [("bsh_read", Scope::Read), ("bsh_write", Scope::TopicWrite)] {
+            db.query(DBQuery::CREATE_ACCESS_TOKEN)
+                .bind(("user", user.id().to_owned()))
+                .bind(("name", token))
+                .bind(("scopes", vec![scope]))
+                .bind(("access_token", token))
+                .bind(("expires_at", None::<surrealdb::sql::Datetime>))
+                .await
+                .unwrap()
+                .check()
+                .unwrap();
+        }
+
+        let create = r#"mutation { topic { create(input: { title: "Title", tags: "", content: "Content" }) } }"#;
+        let mute = r#"mutation { topic { muteIdentity(input: { topic: "missing", identity: 1, muted: true }) } }"#;
+
+        let code = |response: async_graphql::Response| {
+            response
+                .errors
+                .first()
+                .and_then(|error| error.extensions.as_ref())
+                .and_then(|extensions| extensions.get("code").cloned())
+        };
+
+        let response = schema
+            .execute(test_utils::request(create, Some("bsh_read")))
+            .await;
+
+        assert_eq!(code(response), Some(async_graphql::Value::from(403)));
+
+        let response = schema
+            .execute(test_utils::request(create, Some("bsh_write")))
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        // Account settings such as mutes never accept access tokens.
+        let response = schema
+            .execute(test_utils::request(mute, Some("bsh_write")))
+            .await;
+
+        assert_eq!(code(response), Some(async_graphql::Value::from(401)));
+    }
 }
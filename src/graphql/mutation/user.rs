@@ -1,11 +1,21 @@
-use crate::auth::Auth;
+use crate::auth::{Auth, Claims, Tokens};
 use crate::db::defs::{DBQuery, DBTable};
-use crate::db::{defs::SharedDB, table::User};
-use crate::{ClientError, Error, Result};
+use crate::db::{
+    defs::SharedDB,
+    table::{Scope, TopicSort, User, UserSettings},
+};
+use crate::mailer::defs::{Mail, SharedMailer};
+use crate::miscs::generate_token;
+use crate::password::Password;
+use crate::throttle::{ClientIp, LoginThrottle};
+use crate::two_factor::{TwoFactor, TwoFactorSetup};
+use crate::{config, ClientError, Error, Result};
 
-use async_graphql::{Context, InputObject, Object};
-use axum::http::header;
-use cookie::time::Duration;
+use async_graphql::{Context, InputObject, Object, ID};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use surrealdb::sql::{Datetime, Thing};
 use tower_cookies::Cookies;
 use tracing::Instrument;
 
@@ -27,13 +37,117 @@ struct SignUpInput {
     password: String,
 }
 
+#[derive(InputObject, Clone)]
+struct ResetPasswordInput {
+    token: String,
+
+    #[graphql(validator(min_length = 8))]
+    password: String,
+}
+
+#[derive(InputObject, Clone)]
+struct ChangePasswordInput {
+    current_password: Option<String>,
+
+    #[graphql(validator(min_length = 8))]
+    new_password: String,
+}
+
+#[derive(InputObject, Clone)]
+struct ChangeEmailInput {
+    #[graphql(validator(email))]
+    email: String,
+
+    password: Option<String>,
+    code: Option<String>,
+}
+
+#[derive(InputObject, Clone)]
+struct CompleteLoginInput {
+    challenge: String,
+    code: String,
+}
+
+#[derive(InputObject, Clone)]
+struct DisableTwoFactorInput {
+    password: Option<String>,
+    code: String,
+}
+
+#[derive(InputObject, Clone)]
+struct CreateAccessTokenInput {
+    #[graphql(validator(min_length = 1, max_length = 64))]
+    name: String,
+
+    #[graphql(validator(min_items = 1))]
+    scopes: Vec<Scope>,
+
+    #[graphql(validator(minimum = 1, maximum = 365))]
+    expires_in_days: Option<u32>,
+}
+
+#[derive(InputObject, Serialize, Clone)]
+struct UpdateSettingsInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<TopicSort>,
+
+    #[graphql(validator(minimum = 5, maximum = 50))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_size: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    muted_tags: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hide_nsfw: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notify_replies: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notify_likes: Option<bool>,
+}
+
+struct NewAccessToken {
+    id: ID,
+    token: String,
+}
+
+#[Object]
+impl NewAccessToken {
+    async fn id(&self) -> &ID {
+        &self.id
+    }
+
+    async fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+struct LoginPayload {
+    tokens: Option<Tokens>,
+    challenge: Option<String>,
+}
+
+#[Object]
+impl LoginPayload {
+    async fn tokens(&self) -> Option<&Tokens> {
+        self.tokens.as_ref()
+    }
+
+    async fn challenge(&self) -> Option<&str> {
+        self.challenge.as_deref()
+    }
+}
+
 #[derive(Default)]
 pub struct UserMutation;
 
 #[Object]
 impl UserMutation {
-    async fn login(&self, ctx: &Context<'_>, input: LoginInput) -> Result<String> {
+    async fn login(&self, ctx: &Context<'_>, input: LoginInput) -> Result<LoginPayload> {
         let db = ctx.data::<SharedDB>()?;
+        let ip = ctx.data::<ClientIp>()?;
 
         let input_clone = input.clone();
 
@@ -41,31 +155,72 @@ impl UserMutation {
             // Temporary
             tracing::debug!("Attempting");
 
+            let email = input.email.to_lowercase();
+
+            LoginThrottle::check(db, &email, ip)
+                .in_current_span()
+                .await?;
+
             let mut response = db
                 .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
-                .bind(("email", input.email.to_lowercase().to_owned()))
+                .bind(("email", email.to_owned()))
                 .await?;
 
             let Some(user) = response.take::<Option<User>>(0)? else {
-                return Err(Error::Client(ClientError::EmailNotFound));
+                Password::verify_dummy(&input.password);
+
+                LoginThrottle::failure(db, &email, ip)
+                    .in_current_span()
+                    .await?;
+
+                return Err(Self::credentials_error(ClientError::EmailNotFound));
             };
 
-            if !(bcrypt::verify(input.password, user.password())?) {
-                return Err(Error::Client(ClientError::InvalidPassword));
+            if !(Password::verify(&input.password, user.password())?) {
+                LoginThrottle::failure(db, &email, ip)
+                    .in_current_span()
+                    .await?;
+
+                return Err(Self::credentials_error(ClientError::InvalidPassword));
             }
 
-            let token = Auth::generate_jwt(user.id()).in_current_span().await?;
+            if user.is_banned() {
+                return Err(Error::Client(ClientError::Banned));
+            }
 
-            if input.remember_me {
-                let cookie = Auth::cookie(&token);
+            if Password::needs_rehash(user.password())? {
+                Self::rehash_password(db, user.id(), &input.password);
+            }
+
+            if user.is_totp_enabled() {
+                // Temporary
+                tracing::debug!("Two-factor challenge issued");
+
+                let challenge = Auth::generate_challenge(user.id(), input.remember_me).await?;
+
+                return Ok(LoginPayload {
+                    tokens: None,
+                    challenge: Some(challenge),
+                });
+            }
+
+            LoginThrottle::success(db, &email).in_current_span().await?;
+
+            let tokens = Auth::create_session(ctx, user.id(), input.remember_me)
+                .in_current_span()
+                .await?;
 
-                ctx.append_http_header(header::SET_COOKIE, cookie.to_string());
+            if input.remember_me {
+                Auth::append_cookies(ctx, &tokens);
             }
 
             // Temporary
             tracing::debug!("Successful");
 
-            Ok(token)
+            Ok(LoginPayload {
+                tokens: Some(tokens),
+                challenge: None,
+            })
         };
 
         // Temporary
@@ -74,8 +229,74 @@ impl UserMutation {
         future.instrument(span).await
     }
 
-    async fn sign_up(&self, ctx: &Context<'_>, input: SignUpInput) -> Result<String> {
+    async fn complete_login(&self, ctx: &Context<'_>, input: CompleteLoginInput) -> Result<Tokens> {
+        let db = ctx.data::<SharedDB>()?;
+        let ip = ctx.data::<ClientIp>()?;
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Attempting");
+
+            let claims = Auth::validate_challenge(&input.challenge).await?;
+
+            let mut response = db
+                .query(DBQuery::SELECT_ID)
+                .bind(("thing", Thing::from((DBTable::USER, claims.sub()))))
+                .await?;
+
+            let Some(user) = response.take::<Option<User>>(0)? else {
+                return Err(Error::Client(ClientError::Unauthorized));
+            };
+
+            let email = user.email().to_string();
+
+            LoginThrottle::check(db, &email, ip)
+                .in_current_span()
+                .await?;
+
+            let Some(secret) = user.totp_secret().filter(|_| user.is_totp_enabled()) else {
+                return Err(Error::Client(ClientError::Unauthorized));
+            };
+
+            if !(TwoFactor::check(db, user.id(), secret, user.email(), &input.code)
+                .in_current_span()
+                .await?
+                || Self::use_recovery_code(db, user.id(), &input.code)
+                    .in_current_span()
+                    .await?)
+            {
+                LoginThrottle::failure(db, &email, ip)
+                    .in_current_span()
+                    .await?;
+
+                return Err(Error::Client(ClientError::InvalidCode));
+            }
+
+            LoginThrottle::success(db, &email).in_current_span().await?;
+
+            let tokens = Auth::create_session(ctx, user.id(), claims.remember_me())
+                .in_current_span()
+                .await?;
+
+            if claims.remember_me() {
+                Auth::append_cookies(ctx, &tokens);
+            }
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok(tokens)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("CompleteLogin");
+
+        future.instrument(span).await
+    }
+
+    async fn sign_up(&self, ctx: &Context<'_>, input: SignUpInput) -> Result<Tokens> {
         let db = ctx.data::<SharedDB>()?;
+        let mailer = ctx.data::<SharedMailer>()?;
 
         let input_clone = input.clone();
 
@@ -83,33 +304,48 @@ impl UserMutation {
             // Temporary
             tracing::debug!("Attempting");
 
+            if config().HIDE_ACCOUNT_EXISTENCE {
+                // Temporary
+                tracing::debug!("Disabled, requestSignUp must be used");
+
+                return Err(Error::Client(ClientError::Forbidden));
+            }
+
+            let email = input.email.to_lowercase();
+
             let mut response = db
                 .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
-                .bind(("email", input.email.to_owned()))
+                .bind(("email", email.to_owned()))
                 .await?;
 
             if (response.take::<Option<User>>(0)?).is_some() {
                 return Err(Error::Client(ClientError::EmailTaken));
             }
 
-            let password = bcrypt::hash(input.password, bcrypt::DEFAULT_COST)?;
+            let password = Password::hash(&input.password)?;
 
             let mut response = db
                 .query(DBQuery::CREATE_USER)
                 .bind(("password", password))
-                .bind(("email", input.email.to_lowercase().to_owned()))
+                .bind(("email", email.to_owned()))
                 .await?;
 
             let Some(user) = response.take::<Option<User>>(0)? else {
                 return Err(Error::RecordNotCreated(DBTable::USER.to_string()));
             };
 
-            let token = Auth::generate_jwt(user.id()).in_current_span().await?;
+            Self::send_verification(db, mailer, user.id(), user.email())
+                .in_current_span()
+                .await?;
+
+            let tokens = Auth::create_session(ctx, user.id(), false)
+                .in_current_span()
+                .await?;
 
             // Temporary
             tracing::debug!("Successful");
 
-            Ok(token)
+            Ok(tokens)
         };
 
         // Temporary
@@ -118,20 +354,156 @@ impl UserMutation {
         future.instrument(span).await
     }
 
+    /// Sign-up that answers the same way whether or not the email is already
+    /// registered. No session is created; the account is used after logging in.
+    async fn request_sign_up(&self, ctx: &Context<'_>, input: SignUpInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+        let mailer = ctx.data::<SharedMailer>()?;
+
+        let input_clone = input.clone();
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Attempting");
+
+            let email = input.email.to_lowercase();
+            let password = Password::hash(&input.password)?;
+
+            let mut response = db
+                .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
+                .bind(("email", email.to_owned()))
+                .await?;
+
+            if (response.take::<Option<User>>(0)?).is_some() {
+                // Temporary
+                tracing::debug!("Email taken");
+
+                Self::send_account_exists(mailer, &email)
+                    .in_current_span()
+                    .await;
+
+                return Ok("Sign-up requested successfully");
+            }
+
+            let mut response = db
+                .query(DBQuery::CREATE_USER)
+                .bind(("password", password))
+                .bind(("email", email.to_owned()))
+                .await?;
+
+            let Some(user) = response.take::<Option<User>>(0)? else {
+                return Err(Error::RecordNotCreated(DBTable::USER.to_string()));
+            };
+
+            Self::send_verification(db, mailer, user.id(), user.email())
+                .in_current_span()
+                .await?;
+
+            // Temporary
+            tracing::debug!("Successful, awaiting verification");
+
+            Ok("Sign-up requested successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("RequestSignUp", %input_clone.email);
+
+        future.instrument(span).await
+    }
+
+    async fn guest(&self, ctx: &Context<'_>) -> Result<Tokens> {
+        let db = ctx.data::<SharedDB>()?;
+        let ip = ctx.data::<ClientIp>()?;
+
+        let future = async {
+            if !config().ALLOW_GUESTS {
+                // Temporary
+                tracing::debug!("Guest posting disabled");
+
+                return Err(Error::Client(ClientError::Unauthorized));
+            }
+
+            LoginThrottle::guest_created(db, ip)
+                .in_current_span()
+                .await?;
+
+            let mut response = db.query(DBQuery::CREATE_GUEST).await?;
+
+            let Some(user) = response.take::<Option<User>>(0)? else {
+                return Err(Error::RecordNotCreated(DBTable::USER.to_string()));
+            };
+
+            let tokens = Auth::create_session(ctx, user.id(), true)
+                .in_current_span()
+                .await?;
+
+            Auth::append_cookies(ctx, &tokens);
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok(tokens)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("Guest");
+
+        future.instrument(span).await
+    }
+
+    async fn refresh(&self, ctx: &Context<'_>, token: Option<String>) -> Result<Tokens> {
+        let db = ctx.data::<SharedDB>()?;
+        let cookies = ctx.data::<Cookies>()?;
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Attempting");
+
+            let token = token
+                .or_else(|| {
+                    cookies
+                        .get(Auth::REFRESH_COOKIE_NAME)
+                        .map(|cookie| cookie.value().to_string())
+                })
+                .ok_or(Error::Client(ClientError::Unauthorized))?;
+
+            let (session, tokens) = Auth::refresh_session(db, &token).in_current_span().await?;
+
+            if session.remember_me() {
+                Auth::append_cookies(ctx, &tokens);
+            }
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok(tokens)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("Refresh");
+
+        future.instrument(span).await
+    }
+
     async fn logout(&self, ctx: &Context<'_>) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
         let cookies = ctx.data::<Cookies>()?;
 
         let future = async {
-            let Some(mut cookie) = cookies.get(Auth::COOKIE_NAME) else {
+            if let Ok(claims) = Auth::claims(ctx).in_current_span().await {
+                Auth::revoke_session(db, &claims).in_current_span().await?;
+            } else if let Some(cookie) = cookies.get(Auth::REFRESH_COOKIE_NAME) {
+                Auth::revoke_refresh_token(db, cookie.value())
+                    .in_current_span()
+                    .await?;
+            }
+
+            if !Auth::remove_cookies(ctx)? {
                 // Temporary
                 tracing::debug!("User already logged out");
 
                 return Ok("User already logged out");
-            };
-
-            cookie.set_max_age(Duration::ZERO);
-
-            ctx.append_http_header(header::SET_COOKIE, cookie.to_string());
+            }
 
             // Temporary
             tracing::debug!("Successful");
@@ -144,4 +516,1035 @@ impl UserMutation {
         //future.instrument(span).await
         future.instrument(span).await
     }
+
+    async fn revoke_session(&self, ctx: &Context<'_>, id: ID) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let (user, claims) = Auth::authenticate_session(ctx).in_current_span().await?;
+
+            let mut response = db
+                .query(DBQuery::REVOKE_SESSION)
+                .bind(("user", user.id().to_owned()))
+                .bind(("user_session", Thing::from((DBTable::SESSION, id.as_str()))))
+                .await?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                // Temporary
+                tracing::debug!("Session not found");
+
+                return Err(Error::Client(ClientError::SessionNotFound));
+            };
+
+            if claims.sid() == id.as_str() {
+                Auth::remove_cookies(ctx)?;
+            }
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Session revoked successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("RevokeSession", id = %id.as_str());
+
+        future.instrument(span).await
+    }
+
+    async fn revoke_all_sessions(&self, ctx: &Context<'_>) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            db.query(DBQuery::REVOKE_ALL_SESSIONS)
+                .bind(("user", user.id().to_owned()))
+                .await?
+                .check()?;
+
+            Auth::remove_cookies(ctx)?;
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("All sessions revoked successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("RevokeAllSessions");
+
+        future.instrument(span).await
+    }
+
+    async fn update_settings(
+        &self,
+        ctx: &Context<'_>,
+        mut input: UpdateSettingsInput,
+    ) -> Result<UserSettings> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            input.muted_tags = input.muted_tags.map(|tags| {
+                tags.iter()
+                    .map(|tag| tag.trim().trim_start_matches('#').to_lowercase())
+                    .filter(|tag| !tag.is_empty())
+                    .collect::<HashSet<String>>()
+                    .into_iter()
+                    .collect()
+            });
+
+            let mut response = db
+                .query(DBQuery::UPDATE_USER_SETTINGS)
+                .bind((
+                    "settings",
+                    Thing::from((DBTable::USER_SETTINGS, user.id().id.to_raw().as_str())),
+                ))
+                .bind(("changes", input))
+                .await?;
+
+            let Some(settings) = response.take::<Option<UserSettings>>(0)? else {
+                return Err(Error::RecordNotCreated(DBTable::USER_SETTINGS.to_string()));
+            };
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok(settings)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("UpdateSettings");
+
+        future.instrument(span).await
+    }
+
+    async fn create_access_token(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateAccessTokenInput,
+    ) -> Result<NewAccessToken> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            let token = Auth::generate_access_token();
+
+            let expires_at = match input.expires_in_days {
+                Some(days) => Some(Datetime::from(
+                    Utc::now()
+                        .checked_add_signed(Duration::days(days.into()))
+                        .ok_or_else(|| {
+                            Error::Client(ClientError::BadRequest(
+                                "Expiry is out of range".to_string(),
+                            ))
+                        })?,
+                )),
+                None => None,
+            };
+
+            let mut response = db
+                .query(DBQuery::CREATE_ACCESS_TOKEN)
+                .bind(("user", user.id().to_owned()))
+                .bind(("name", input.name))
+                .bind(("scopes", input.scopes))
+                .bind(("access_token", token.to_owned()))
+                .bind(("expires_at", expires_at))
+                .await?;
+
+            let Some(id) = response.take::<Option<ID>>(0)? else {
+                return Err(Error::RecordNotCreated(DBTable::ACCESS_TOKEN.to_string()));
+            };
+
+            // Temporary
+            tracing::debug!(id = %id.as_str(), "Successful");
+
+            Ok(NewAccessToken { id, token })
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("CreateAccessToken");
+
+        future.instrument(span).await
+    }
+
+    async fn revoke_access_token(&self, ctx: &Context<'_>, id: ID) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            let mut response = db
+                .query(DBQuery::REVOKE_ACCESS_TOKEN)
+                .bind(("user", user.id().to_owned()))
+                .bind((
+                    "access_token",
+                    Thing::from((DBTable::ACCESS_TOKEN, id.as_str())),
+                ))
+                .await?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                // Temporary
+                tracing::debug!("Access token not found");
+
+                return Err(Error::Client(ClientError::AccessTokenNotFound));
+            };
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Access token revoked successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("RevokeAccessToken", id = %id.as_str());
+
+        future.instrument(span).await
+    }
+
+    async fn request_password_reset(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(email))] email: String,
+    ) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+        let mailer = ctx.data::<SharedMailer>()?;
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Attempting");
+
+            let mut response = db
+                .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
+                .bind(("email", email.to_lowercase()))
+                .await?;
+
+            let Some(user) = response
+                .take::<Option<User>>(0)?
+                .filter(|user| !user.is_guest())
+            else {
+                // Temporary
+                tracing::debug!("User not found");
+
+                return Ok("Password reset requested successfully");
+            };
+
+            let token = generate_token(Self::RESET_TOKEN_LENGTH);
+
+            db.query(DBQuery::CREATE_PASSWORD_RESET)
+                .bind(("user", user.id().to_owned()))
+                .bind(("reset_token", token.to_owned()))
+                .bind((
+                    "expires_at",
+                    Datetime::from(Utc::now() + Duration::minutes(Self::RESET_TOKEN_MINUTES)),
+                ))
+                .await?
+                .check()?;
+
+            let body = format!(
+                "Someone requested a password reset for your Basher account.\n\n\
+                Reset your password within {} minutes using the link below:\n\n\
+                {}/reset-password?token={}\n\n\
+                If you did not request this, you can ignore this email.",
+                Self::RESET_TOKEN_MINUTES,
+                config().APP_URL,
+                token
+            );
+
+            let mail = Mail::new(&email, "Reset your Basher password", body);
+
+            if let Err(e) = mailer.send(mail).await {
+                tracing::error!("Mail not sent: {e:?}");
+            }
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Password reset requested successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("RequestPasswordReset", %email);
+
+        future.instrument(span).await
+    }
+
+    async fn reset_password(&self, ctx: &Context<'_>, input: ResetPasswordInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Attempting");
+
+            let password = Password::hash(&input.password)?;
+
+            let mut response = db
+                .query(DBQuery::RESET_PASSWORD)
+                .bind(("reset_token", input.token))
+                .bind(("password", password))
+                .await?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                // Temporary
+                tracing::debug!("Invalid token");
+
+                return Err(Error::Client(ClientError::InvalidToken));
+            };
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Password reset successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("ResetPassword");
+
+        future.instrument(span).await
+    }
+
+    async fn verify_email(&self, ctx: &Context<'_>, token: String) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Attempting");
+
+            let mut response = db
+                .query(DBQuery::VERIFY_EMAIL)
+                .bind(("verification_token", token))
+                .await?;
+
+            Self::check_email_taken(&mut response)?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                // Temporary
+                tracing::debug!("Invalid token");
+
+                return Err(Error::Client(ClientError::InvalidToken));
+            };
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Email verified successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("VerifyEmail");
+
+        future.instrument(span).await
+    }
+
+    async fn resend_verification(&self, ctx: &Context<'_>) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+        let mailer = ctx.data::<SharedMailer>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            if user.is_guest() {
+                return Err(Error::Client(ClientError::Forbidden));
+            }
+
+            if user.is_verified() {
+                // Temporary
+                tracing::debug!("Email already verified");
+
+                return Ok("Email already verified");
+            }
+
+            Self::send_verification(db, mailer, user.id(), user.email())
+                .in_current_span()
+                .await?;
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Verification sent successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("ResendVerification");
+
+        future.instrument(span).await
+    }
+
+    async fn change_password(&self, ctx: &Context<'_>, input: ChangePasswordInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let (user, claims) = Auth::authenticate_session(ctx).in_current_span().await?;
+
+            Self::reauthenticate(db, &user, &claims, input.current_password.as_deref(), None)
+                .in_current_span()
+                .await?;
+
+            let password = Password::hash(&input.new_password)?;
+
+            db.query(DBQuery::UPDATE_PASSWORD)
+                .bind(("user", user.id().to_owned()))
+                .bind((
+                    "user_session",
+                    Thing::from((DBTable::SESSION, claims.sid())),
+                ))
+                .bind(("password", password))
+                .await?
+                .check()?;
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Password changed successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("ChangePassword");
+
+        future.instrument(span).await
+    }
+
+    async fn change_email(&self, ctx: &Context<'_>, input: ChangeEmailInput) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+        let mailer = ctx.data::<SharedMailer>()?;
+
+        let input_clone = input.clone();
+
+        let future = async {
+            let (user, claims) = Auth::authenticate_session(ctx).in_current_span().await?;
+
+            Self::reauthenticate(
+                db,
+                &user,
+                &claims,
+                input.password.as_deref(),
+                input.code.as_deref(),
+            )
+            .in_current_span()
+            .await?;
+
+            let email = input.email.to_lowercase();
+
+            if email == user.email() {
+                return Err(Error::Client(ClientError::BadRequest(
+                    "New email must differ from the current one".to_string(),
+                )));
+            }
+
+            let mut response = db
+                .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
+                .bind(("email", email.to_owned()))
+                .await?;
+
+            if response.take::<Option<User>>(0)?.is_some() {
+                return Err(Error::Client(ClientError::EmailTaken));
+            }
+
+            // The current address stays in place until `verifyEmail` confirms
+            // the pending one stored on the verification.
+            Self::send_verification(db, mailer, user.id(), &email)
+                .in_current_span()
+                .await?;
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Verification sent to the new email")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("ChangeEmail", %input_clone.email);
+
+        future.instrument(span).await
+    }
+
+    async fn enable_two_factor(
+        &self,
+        ctx: &Context<'_>,
+        password: Option<String>,
+        code: Option<String>,
+    ) -> Result<TwoFactorSetup> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let (user, claims) = Auth::authenticate_session(ctx).in_current_span().await?;
+
+            Self::reauthenticate(db, &user, &claims, password.as_deref(), None)
+                .in_current_span()
+                .await?;
+
+            // Replacing an active secret also needs proof of the current one.
+            if let Some(secret) = user.totp_secret().filter(|_| user.is_totp_enabled()) {
+                let code = code.as_deref().unwrap_or_default();
+
+                if !(TwoFactor::check(db, user.id(), secret, user.email(), code)
+                    .in_current_span()
+                    .await?
+                    || Self::use_recovery_code(db, user.id(), code)
+                        .in_current_span()
+                        .await?)
+                {
+                    return Err(Error::Client(ClientError::InvalidCode));
+                }
+            }
+
+            let secret = TwoFactor::generate_secret();
+            let setup = TwoFactor::setup(&secret, user.email())?;
+
+            db.query(DBQuery::SET_PENDING_TOTP)
+                .bind(("user", user.id().to_owned()))
+                .bind(("secret", secret))
+                .await?
+                .check()?;
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok(setup)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("EnableTwoFactor");
+
+        future.instrument(span).await
+    }
+
+    async fn confirm_two_factor(&self, ctx: &Context<'_>, code: String) -> Result<Vec<String>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            let Some(secret) = user.totp_pending_secret() else {
+                // Temporary
+                tracing::debug!("No pending secret");
+
+                return Err(Error::Client(ClientError::InvalidCode));
+            };
+
+            if !(TwoFactor::check(db, user.id(), secret, user.email(), &code)
+                .in_current_span()
+                .await?)
+            {
+                return Err(Error::Client(ClientError::InvalidCode));
+            }
+
+            let codes = TwoFactor::recovery_codes();
+
+            let mut response = db
+                .query(DBQuery::ENABLE_TOTP)
+                .bind(("user", user.id().to_owned()))
+                .bind(("secret", secret.to_owned()))
+                .bind(("codes", codes.to_owned()))
+                .await?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                return Err(Error::Client(ClientError::InvalidCode));
+            };
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok(codes)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("ConfirmTwoFactor");
+
+        future.instrument(span).await
+    }
+
+    async fn disable_two_factor(
+        &self,
+        ctx: &Context<'_>,
+        input: DisableTwoFactorInput,
+    ) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let (user, claims) = Auth::authenticate_session(ctx).in_current_span().await?;
+
+            Self::reauthenticate(db, &user, &claims, input.password.as_deref(), None)
+                .in_current_span()
+                .await?;
+
+            let Some(secret) = user.totp_secret().filter(|_| user.is_totp_enabled()) else {
+                // Temporary
+                tracing::debug!("Two-factor already disabled");
+
+                return Ok("Two-factor already disabled");
+            };
+
+            if !(TwoFactor::check(db, user.id(), secret, user.email(), &input.code)
+                .in_current_span()
+                .await?
+                || Self::use_recovery_code(db, user.id(), &input.code)
+                    .in_current_span()
+                    .await?)
+            {
+                return Err(Error::Client(ClientError::InvalidCode));
+            }
+
+            db.query(DBQuery::DISABLE_TOTP)
+                .bind(("user", user.id().to_owned()))
+                .await?
+                .check()?;
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Two-factor disabled successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("DisableTwoFactor");
+
+        future.instrument(span).await
+    }
+
+    async fn delete_account(
+        &self,
+        ctx: &Context<'_>,
+        password: Option<String>,
+        code: Option<String>,
+    ) -> Result<&str> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let (user, claims) = Auth::authenticate_session(ctx).in_current_span().await?;
+
+            Self::reauthenticate(db, &user, &claims, password.as_deref(), code.as_deref())
+                .in_current_span()
+                .await?;
+
+            let mut response = db
+                .query(DBQuery::DELETE_ACCOUNT)
+                .bind(("user", user.id().to_owned()))
+                .await?;
+
+            let Some(_) = response.take::<Option<ID>>(0)? else {
+                return Err(Error::Client(ClientError::Unauthorized));
+            };
+
+            Auth::remove_cookies(ctx)?;
+
+            // Temporary
+            tracing::debug!("Successful");
+
+            Ok("Account deleted successfully")
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("DeleteAccount");
+
+        future.instrument(span).await
+    }
+}
+
+impl UserMutation {
+    const RESET_TOKEN_LENGTH: usize = 64;
+    const RESET_TOKEN_MINUTES: i64 = 60;
+
+    const VERIFICATION_TOKEN_LENGTH: usize = 64;
+    const VERIFICATION_TOKEN_HOURS: i64 = 24;
+
+    const EMAIL_INDEX: &'static str = "user_email_index";
+
+    const REAUTHENTICATION_MINUTES: i64 = 10;
+
+    /// Confirms a sensitive change with the account's password. Accounts
+    /// without one (OIDC and guests) use a TOTP or recovery code instead, or a
+    /// session opened in the last few minutes, e.g. by signing in through OIDC
+    /// again. A guest's session is its only credential, so it is enough.
+    async fn reauthenticate(
+        db: &SharedDB,
+        user: &User,
+        claims: &Claims,
+        password: Option<&str>,
+        code: Option<&str>,
+    ) -> Result<()> {
+        if !user.password().is_empty() {
+            return match Password::verify(password.unwrap_or_default(), user.password())? {
+                true => Ok(()),
+                false => Err(Error::Client(ClientError::InvalidPassword)),
+            };
+        }
+
+        if user.is_guest() {
+            return Ok(());
+        }
+
+        if let (Some(secret), Some(code)) =
+            (user.totp_secret().filter(|_| user.is_totp_enabled()), code)
+        {
+            if TwoFactor::check(db, user.id(), secret, user.email(), code)
+                .in_current_span()
+                .await?
+                || Self::use_recovery_code(db, user.id(), code)
+                    .in_current_span()
+                    .await?
+            {
+                return Ok(());
+            }
+
+            return Err(Error::Client(ClientError::InvalidCode));
+        }
+
+        let mut response = db
+            .query(DBQuery::SELECT_RECENT_SESSION)
+            .bind(("user", user.id().to_owned()))
+            .bind((
+                "user_session",
+                Thing::from((DBTable::SESSION, claims.sid())),
+            ))
+            .bind((
+                "since",
+                Datetime::from(Utc::now() - Duration::minutes(Self::REAUTHENTICATION_MINUTES)),
+            ))
+            .await?;
+
+        match response.take::<Option<Thing>>(0)? {
+            Some(_) => Ok(()),
+            None => Err(Error::Client(ClientError::ReauthenticationRequired)),
+        }
+    }
+
+    /// Another account can claim a pending email before it is verified, in
+    /// which case the unique index rejects the whole transaction.
+    fn check_email_taken(response: &mut surrealdb::Response) -> Result<()> {
+        let errors = response.take_errors();
+
+        if errors
+            .values()
+            .any(|e| e.to_string().contains(Self::EMAIL_INDEX))
+        {
+            return Err(Error::Client(ClientError::EmailTaken));
+        }
+
+        match errors.into_values().next() {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+
+    async fn send_verification(
+        db: &SharedDB,
+        mailer: &SharedMailer,
+        user: &Thing,
+        email: &str,
+    ) -> Result<()> {
+        // Temporary
+        tracing::debug!("Sending verification");
+
+        let token = generate_token(Self::VERIFICATION_TOKEN_LENGTH);
+
+        db.query(DBQuery::CREATE_EMAIL_VERIFICATION)
+            .bind(("user", user.to_owned()))
+            .bind(("email", email.to_owned()))
+            .bind(("verification_token", token.to_owned()))
+            .bind((
+                "expires_at",
+                Datetime::from(Utc::now() + Duration::hours(Self::VERIFICATION_TOKEN_HOURS)),
+            ))
+            .await?
+            .check()?;
+
+        let body = format!(
+            "Welcome to Basher!\n\n\
+            Verify your email address within {} hours using the link below:\n\n\
+            {}/verify-email?token={}\n\n\
+            If you did not create an account, you can ignore this email.",
+            Self::VERIFICATION_TOKEN_HOURS,
+            config().APP_URL,
+            token
+        );
+
+        let mail = Mail::new(email, "Verify your Basher email", body);
+
+        if let Err(e) = mailer.send(mail).await {
+            tracing::error!("Mail not sent: {e:?}");
+        }
+
+        Ok(())
+    }
+
+    /// Upgrades a legacy hash off the request path, so the response time of a
+    /// login doesn't reveal which accounts still use one.
+    fn rehash_password(db: &SharedDB, user: &Thing, password: &str) {
+        let db = db.clone();
+        let user = user.to_owned();
+        let password = password.to_owned();
+
+        let future = async move {
+            // Temporary
+            tracing::debug!("Rehashing password");
+
+            let result = async {
+                db.query(DBQuery::REHASH_PASSWORD)
+                    .bind(("user", user))
+                    .bind(("password", Password::hash(&password)?))
+                    .await?
+                    .check()?;
+
+                Ok::<_, Error>(())
+            };
+
+            if let Err(e) = result.await {
+                tracing::error!("Password not rehashed: {e:?}");
+            }
+        };
+
+        tokio::spawn(future.in_current_span());
+    }
+
+    async fn send_account_exists(mailer: &SharedMailer, email: &str) {
+        let body = format!(
+            "Someone tried to create a Basher account with this email address, \
+            but an account already exists.\n\n\
+            If this was you, log in or reset your password at:\n\n\
+            {}/reset-password\n\n\
+            If you did not try to sign up, you can ignore this email.",
+            config().APP_URL
+        );
+
+        let mail = Mail::new(email, "You already have a Basher account", body);
+
+        if let Err(e) = mailer.send(mail).await {
+            tracing::error!("Mail not sent: {e:?}");
+        }
+    }
+
+    fn credentials_error(error: ClientError) -> Error {
+        match config().HIDE_ACCOUNT_EXISTENCE {
+            true => Error::Client(ClientError::InvalidCredentials),
+            false => Error::Client(error),
+        }
+    }
+
+    async fn use_recovery_code(db: &SharedDB, user: &Thing, code: &str) -> Result<bool> {
+        // Temporary
+        tracing::debug!("Checking recovery code");
+
+        let mut response = db
+            .query(DBQuery::USE_RECOVERY_CODE)
+            .bind(("user", user.to_owned()))
+            .bind(("code", code.trim().to_lowercase()))
+            .await?;
+
+        Ok(response.take::<Option<ID>>(0)?.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailer::{defs::SharedMailer, file::FileMailer};
+    use crate::test_utils;
+
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn file_mailer() -> (SharedMailer, PathBuf) {
+        let path = std::env::temp_dir().join(format!(
+            "basher-mail-{}.log",
+            surrealdb::sql::Id::rand().to_raw()
+        ));
+        let mailer: SharedMailer =
+            Arc::new(FileMailer::new(Some(path.to_string_lossy().into_owned())));
+
+        (mailer, path)
+    }
+
+    async fn bearer(db: &SharedDB, user: &User) -> String {
+        Auth::open_session(db, user.id(), false, None)
+            .await
+            .unwrap();
+
+        let session = db
+            .query("SELECT VALUE id FROM session WHERE user = $user AND time.created_at > time::now() - 1m LIMIT 1")
+            .bind(("user", user.id().to_owned()))
+            .await
+            .unwrap()
+            .take::<Option<Thing>>(0)
+            .unwrap()
+            .unwrap();
+
+        Auth::generate_jwt(user.id(), &session).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn password_reset_is_delivered_through_the_file_mailer() {
+        let db = test_utils::db().await;
+
+        let (mailer, path) = file_mailer();
+        let schema = test_utils::schema(&db, &mailer);
+
+        test_utils::user(&db, &Password::hash("old password").unwrap()).await;
+
+        let response = schema
+            .execute(r#"mutation { user { requestPasswordReset(email: "alice@example.com") } }"#)
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let mail = tokio::fs::read_to_string(&path).await.unwrap();
+        let _ = tokio::fs::remove_file(&path).await;
+
+        assert!(mail.starts_with("To: alice@example.com\n"));
+
+        let token = mail
+            .split("token=")
+            .nth(1)
+            .and_then(|rest| rest.lines().next())
+            .unwrap();
+
+        let reset = format!(
+            r#"mutation {{ user {{ resetPassword(input: {{ token: "{token}", password: "new password" }}) }} }}"#
+        );
+
+        let response = schema.execute(reset.as_str()).await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        // Reset tokens are single use.
+        let response = schema.execute(reset.as_str()).await;
+
+        assert!(!response.errors.is_empty());
+
+        let user = db
+            .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
+            .bind(("email", test_utils::EMAIL))
+            .await
+            .unwrap()
+            .take::<Option<User>>(0)
+            .unwrap()
+            .unwrap();
+
+        assert!(Password::verify("new password", user.password()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn sign_up_verification_marks_the_email_verified() {
+        let db = test_utils::db().await;
+
+        let (mailer, path) = file_mailer();
+        let schema = test_utils::schema(&db, &mailer);
+
+        let response = schema
+            .execute(test_utils::request(
+                r#"mutation { user { signUp(input: { email: "alice@example.com", password: "password" }) { accessToken } } }"#,
+                None,
+            ))
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let mail = tokio::fs::read_to_string(&path).await.unwrap();
+        let _ = tokio::fs::remove_file(&path).await;
+
+        let token = mail
+            .split("token=")
+            .nth(1)
+            .and_then(|rest| rest.lines().next())
+            .unwrap();
+
+        let verify = format!(r#"mutation {{ user {{ verifyEmail(token: "{token}") }} }}"#);
+
+        let response = schema.execute(verify.as_str()).await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let user = db
+            .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
+            .bind(("email", test_utils::EMAIL))
+            .await
+            .unwrap()
+            .take::<Option<User>>(0)
+            .unwrap()
+            .unwrap();
+
+        assert!(user.is_verified());
+    }
+
+    #[tokio::test]
+    async fn password_less_accounts_delete_with_a_recent_session() {
+        let db = test_utils::db().await;
+
+        let mailer: SharedMailer = Arc::new(FileMailer::new(None));
+        let schema = test_utils::schema(&db, &mailer);
+
+        let user = test_utils::user(&db, "").await;
+        let delete = "mutation { user { deleteAccount } }";
+
+        let stale = bearer(&db, &user).await;
+
+        db.query("UPDATE session SET time.created_at = time::now() - 1h WHERE user = $user")
+            .bind(("user", user.id().to_owned()))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let response = schema
+            .execute(test_utils::request(delete, Some(&stale)))
+            .await;
+
+        assert_eq!(response.errors[0].message, "REAUTHENTICATION_REQUIRED");
+
+        // Signing in again, e.g. through OIDC, opens a fresh session.
+        let fresh = bearer(&db, &user).await;
+
+        let response = schema
+            .execute(test_utils::request(delete, Some(&fresh)))
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let response = db
+            .query(DBQuery::SELECT_ONLY_USER_FROM_EMAIL)
+            .bind(("email", test_utils::EMAIL))
+            .await
+            .unwrap()
+            .take::<Option<User>>(0)
+            .unwrap();
+
+        assert!(response.is_none());
+    }
+
+    #[tokio::test]
+    async fn guests_delete_with_their_session() {
+        let db = test_utils::db().await;
+
+        let mailer: SharedMailer = Arc::new(FileMailer::new(None));
+        let schema = test_utils::schema(&db, &mailer);
+
+        let guest = db
+            .query(DBQuery::CREATE_GUEST)
+            .await
+            .unwrap()
+            .take::<Option<User>>(0)
+            .unwrap()
+            .unwrap();
+
+        let token = bearer(&db, &guest).await;
+
+        db.query("UPDATE session SET time.created_at = time::now() - 1d WHERE user = $user")
+            .bind(("user", guest.id().to_owned()))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let response = schema
+            .execute(test_utils::request(
+                "mutation { user { deleteAccount } }",
+                Some(&token),
+            ))
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+    }
 }
@@ -1,4 +1,5 @@
 mod reply;
+mod tag;
 mod topic;
 mod user;
 
@@ -20,4 +21,8 @@ impl RootQuery {
     async fn user(&self) -> user::UserQuery {
         Default::default()
     }
+
+    async fn tag(&self) -> tag::TagQuery {
+        Default::default()
+    }
 }
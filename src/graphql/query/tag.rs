@@ -0,0 +1,109 @@
+use crate::db::defs::{DBQuery, SharedDB};
+use crate::db::table::TagUsage;
+use crate::Result;
+
+use async_graphql::{Context, Object};
+use chrono::{Duration, Utc};
+use surrealdb::sql::Datetime;
+use tracing::Instrument;
+
+#[derive(Default)]
+pub struct TagQuery;
+
+#[Object]
+impl TagQuery {
+    async fn autocomplete(&self, ctx: &Context<'_>, prefix: String) -> Result<Vec<TagUsage>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let prefix = prefix.trim().trim_start_matches('#').to_lowercase();
+
+        let future = async {
+            if prefix.chars().count() < Self::MIN_PREFIX_LENGTH {
+                // Temporary
+                tracing::debug!("Prefix too short");
+
+                return Ok(Vec::new());
+            }
+
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let mut response = db
+                .query(DBQuery::SELECT_TAGS_FROM_PREFIX)
+                .bind(("prefix", prefix.to_owned()))
+                .await?;
+
+            let tags = response.take::<Vec<TagUsage>>(0)?;
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(tags)
+        };
+
+        let span = tracing::debug_span!("Autocomplete", %prefix);
+
+        future.instrument(span).await
+    }
+
+    async fn popular(&self, ctx: &Context<'_>, offset: u64) -> Result<Vec<TagUsage>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let mut response = db
+                .query(DBQuery::SELECT_POPULAR_TAGS)
+                .bind(("offset", offset))
+                .await?;
+
+            let tags = response.take::<Vec<TagUsage>>(0)?;
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(tags)
+        };
+
+        let span = tracing::debug_span!("Popular");
+
+        future.instrument(span).await
+    }
+
+    async fn trending(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 7, validator(minimum = 1, maximum = 90))] days: u32,
+    ) -> Result<Vec<TagUsage>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let window = Duration::days(days.into());
+
+        let future = async {
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let mut response = db
+                .query(DBQuery::SELECT_TRENDING_TAGS)
+                .bind(("since", Datetime::from(Utc::now() - window)))
+                .bind(("previous", Datetime::from(Utc::now() - window * 2)))
+                .await?;
+
+            let tags = response.take::<Vec<TagUsage>>(0)?;
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(tags)
+        };
+
+        let span = tracing::debug_span!("Trending", %days);
+
+        future.instrument(span).await
+    }
+}
+
+impl TagQuery {
+    const MIN_PREFIX_LENGTH: usize = 2;
+}
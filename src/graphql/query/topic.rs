@@ -1,6 +1,7 @@
 use crate::auth::Auth;
 use crate::db::defs::{DBQuery, DBTable, SharedDB};
 use crate::db::table::Topic;
+use crate::graphql::defs::load_settings;
 use crate::Result;
 
 use async_graphql::{Context, InputObject, Object, ID};
@@ -29,6 +30,8 @@ impl TopicQuery {
                 .await
                 .unwrap_or_default();
 
+            let settings = load_settings(db, user.id()).await?;
+
             // Temporary
             tracing::debug!("Retrieving data");
 
@@ -36,6 +39,9 @@ impl TopicQuery {
                 .query(DBQuery::SELECT_TOPICS)
                 .bind(("offset", offset))
                 .bind(("user", user.id().to_owned()))
+                .bind(("sort", settings.topic_sort()))
+                .bind(("limit", settings.limit()))
+                .bind(("hidden_tags", settings.hidden_tags()))
                 .await?;
 
             // Temporary
@@ -88,12 +94,23 @@ impl TopicQuery {
         let input_clone = input.clone();
 
         let future = async {
+            let user = Auth::authenticate(ctx)
+                .in_current_span()
+                .await
+                .unwrap_or_default();
+
+            let settings = load_settings(db, user.id()).await?;
+
             // Temporary
             tracing::debug!("Searching data");
 
             let mut response = db
                 .query(DBQuery::SELECT_TOPICS_FROM_QUERY)
                 .bind(("offset", input.offset))
+                .bind(("user", user.id().to_owned()))
+                .bind(("sort", settings.topic_sort()))
+                .bind(("limit", settings.limit()))
+                .bind(("hidden_tags", settings.hidden_tags()))
                 .bind(("query", input.query.to_owned()))
                 .bind((
                     "tags",
@@ -120,3 +137,83 @@ impl TopicQuery {
         future.instrument(span).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::db::defs::DBQuery;
+    use crate::db::table::Scope;
+    use crate::mailer::{defs::SharedMailer, file::FileMailer};
+    use crate::test_utils;
+
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn search_ranks_matches_before_the_sort_preference() {
+        let db = test_utils::db().await;
+
+        let mailer: SharedMailer = Arc::new(FileMailer::new(None));
+        let schema = test_utils::schema(&db, &mailer);
+
+        let user = test_utils::user(&db, "").await;
+
+        db.query(DBQuery::CREATE_ACCESS_TOKEN)
+            .bind(("user", user.id().to_owned()))
+            .bind(("name", "bsh_write"))
+            .bind(("scopes", vec![Scope::TopicWrite]))
+            .bind(("access_token", "bsh_write"))
+            .bind(("expires_at", None::<surrealdb::sql::Datetime>))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        for title in ["Rust borrow checker", "Cooking with rust", "Gardening"] {
+            let create = format!(
+                r#"mutation {{ topic {{ create(input: {{ title: "{title}", tags: "", content: "Content" }}) }} }}"#
+            );
+
+            let response = schema
+                .execute(test_utils::request(&create, Some("bsh_write")))
+                .await;
+
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+        }
+
+        let titles = |response: async_graphql::Response| {
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+            let data = response.data.into_json().unwrap();
+
+            data["topic"]["search"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|topic| topic["title"].as_str().unwrap().to_owned())
+                .collect::<Vec<String>>()
+        };
+
+        // Without a query every score ties and the newest topic comes first.
+        let search =
+            r#"query { topic { search(input: { query: "", tags: "", offset: 0 }) { title } } }"#;
+
+        let response = schema
+            .execute(test_utils::request(search, Some("bsh_write")))
+            .await;
+
+        assert_eq!(
+            titles(response),
+            ["Gardening", "Cooking with rust", "Rust borrow checker"]
+        );
+
+        let search = r#"query { topic { search(input: { query: "rust", tags: "", offset: 0 }) { title } } }"#;
+
+        let response = schema
+            .execute(test_utils::request(search, Some("bsh_write")))
+            .await;
+
+        let found = titles(response);
+
+        assert_eq!(found.len(), 2);
+        assert!(!found.contains(&"Gardening".to_owned()));
+    }
+}
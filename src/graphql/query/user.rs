@@ -1,9 +1,37 @@
 use crate::auth::Auth;
-use crate::Result;
+use crate::db::defs::{DBQuery, DBTable, SharedDB};
+use crate::db::table::{AccessToken, ActiveSession, Profile, Reply, Topic, UserSettings};
+use crate::graphql::defs::load_settings;
+use crate::{ClientError, Error, Result};
 
-use async_graphql::{Context, Object};
+use async_graphql::{Context, Json, Object};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
 use tracing::Instrument;
 
+#[derive(Deserialize)]
+struct Account {
+    email: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Post {
+    id: String,
+    class: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Export {
+    email: String,
+    created_at: DateTime<Utc>,
+    topics: Vec<Topic>,
+    replies: Vec<Reply>,
+    likes: Vec<Post>,
+    shares: Vec<Post>,
+}
+
 #[derive(Default)]
 pub struct UserQuery;
 
@@ -22,4 +50,155 @@ impl UserQuery {
 
         future.instrument(span).await
     }
+
+    async fn me(&self, ctx: &Context<'_>) -> Result<Profile> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate(ctx).in_current_span().await?;
+
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let mut response = db
+                .query(DBQuery::SELECT_PROFILE)
+                .bind(("user", user.id().to_owned()))
+                .await?;
+
+            let Some(profile) = response.take::<Option<Profile>>(0)? else {
+                return Err(Error::Client(ClientError::Unauthorized));
+            };
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(profile)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("Me");
+
+        future.instrument(span).await
+    }
+
+    async fn settings(&self, ctx: &Context<'_>) -> Result<UserSettings> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate(ctx).in_current_span().await?;
+
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let settings = load_settings(db, user.id()).await?;
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(settings)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("Settings");
+
+        future.instrument(span).await
+    }
+
+    async fn sessions(&self, ctx: &Context<'_>) -> Result<Vec<ActiveSession>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let (user, claims) = Auth::authenticate_session(ctx).in_current_span().await?;
+
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let mut response = db
+                .query(DBQuery::SELECT_SESSIONS)
+                .bind(("user", user.id().to_owned()))
+                .bind((
+                    "user_session",
+                    Thing::from((DBTable::SESSION, claims.sid())),
+                ))
+                .await?;
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(response.take::<Vec<ActiveSession>>(0)?)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("Sessions");
+
+        future.instrument(span).await
+    }
+
+    async fn access_tokens(&self, ctx: &Context<'_>) -> Result<Vec<AccessToken>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let mut response = db
+                .query(DBQuery::SELECT_ACCESS_TOKENS)
+                .bind(("user", user.id().to_owned()))
+                .await?;
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(response.take::<Vec<AccessToken>>(0)?)
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("AccessTokens");
+
+        future.instrument(span).await
+    }
+
+    async fn export(&self, ctx: &Context<'_>) -> Result<Json<Export>> {
+        let db = ctx.data::<SharedDB>()?;
+
+        let future = async {
+            let user = Auth::authenticate_account(ctx).in_current_span().await?;
+
+            // Temporary
+            tracing::debug!("Retrieving data");
+
+            let mut response = db
+                .query(DBQuery::SELECT_USER_EXPORT)
+                .bind(("user", user.id().to_owned()))
+                .await?;
+
+            let Some(account) = response.take::<Option<Account>>(0)? else {
+                return Err(Error::Client(ClientError::Unauthorized));
+            };
+
+            let topics = response.take::<Vec<Topic>>(1)?;
+            let replies = response.take::<Vec<Reply>>(2)?;
+            let likes = response.take::<Vec<Post>>(3)?;
+            let shares = response.take::<Vec<Post>>(4)?;
+
+            // Temporary
+            tracing::debug!("Data retrieved");
+
+            Ok(Json(Export {
+                email: account.email,
+                created_at: account.created_at,
+                topics,
+                replies,
+                likes,
+                shares,
+            }))
+        };
+
+        // Temporary
+        let span = tracing::debug_span!("Export");
+
+        future.instrument(span).await
+    }
 }
@@ -0,0 +1,215 @@
+use crate::{config, Error, Result};
+
+use axum::Json;
+use jsonwebtoken::{
+    jwk::{AlgorithmParameters, Jwk, JwkSet, KeyAlgorithm},
+    Algorithm, DecodingKey, EncodingKey, Header,
+};
+use std::{str::FromStr, sync::OnceLock};
+
+static INSTANCE: OnceLock<Keys> = OnceLock::new();
+
+pub fn keys() -> &'static Keys {
+    INSTANCE.get_or_init(|| {
+        Keys::load_from_config().unwrap_or_else(|e| panic!("Failed to load keys: {e:?}"))
+    })
+}
+
+/// Loads the keys at startup so that a bad configuration stops the server
+/// instead of panicking on the first request that signs a token.
+pub fn init() -> Result<()> {
+    if INSTANCE.get().is_none() {
+        let _ = INSTANCE.set(Keys::load_from_config()?);
+    }
+
+    Ok(())
+}
+
+pub async fn jwks_handler() -> Json<JwkSet> {
+    Json(keys().jwks.clone())
+}
+
+struct VerifyingKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+pub struct Keys {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    signing: EncodingKey,
+    verifying: Vec<VerifyingKey>,
+    jwks: JwkSet,
+}
+
+impl Keys {
+    pub fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+
+        header.kid = self.kid.clone();
+
+        header
+    }
+
+    pub fn signing(&self) -> &EncodingKey {
+        &self.signing
+    }
+
+    pub fn verifying(&self, kid: Option<&str>) -> Option<(Algorithm, &DecodingKey)> {
+        self.verifying
+            .iter()
+            .find(|key| key.kid.as_deref() == kid)
+            .map(|key| (key.algorithm, &key.key))
+    }
+}
+
+impl Keys {
+    fn load_from_config() -> Result<Self> {
+        let cfg = config();
+
+        let algorithm = Algorithm::from_str(&cfg.JWT_ALGORITHM)?;
+
+        if matches!(
+            algorithm,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            let secret = cfg
+                .JWT_SECRET
+                .as_deref()
+                .ok_or_else(|| Error::MissingEnv("JWT_SECRET".to_string()))?;
+
+            return Ok(Self {
+                kid: None,
+                algorithm,
+                signing: EncodingKey::from_secret(secret.as_bytes()),
+                verifying: vec![VerifyingKey {
+                    kid: None,
+                    algorithm,
+                    key: DecodingKey::from_secret(secret.as_bytes()),
+                }],
+                jwks: JwkSet { keys: Vec::new() },
+            });
+        }
+
+        let kid = cfg
+            .JWT_KID
+            .clone()
+            .ok_or_else(|| Error::MissingEnv("JWT_KID".to_string()))?;
+        let private_key = cfg
+            .JWT_PRIVATE_KEY
+            .as_deref()
+            .ok_or_else(|| Error::MissingEnv("JWT_PRIVATE_KEY".to_string()))?;
+        let jwks = cfg
+            .JWT_JWKS
+            .as_deref()
+            .ok_or_else(|| Error::MissingEnv("JWT_JWKS".to_string()))?;
+
+        let signing = match algorithm {
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(private_key.as_bytes())?,
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => {
+                EncodingKey::from_rsa_pem(private_key.as_bytes())?
+            }
+            _ => return Err(Error::InvalidKey(format!("{algorithm:?} is not supported"))),
+        };
+
+        let jwks = serde_json::from_str::<JwkSet>(jwks)?;
+
+        let verifying = jwks
+            .keys
+            .iter()
+            .map(Self::verifying_key)
+            .collect::<Result<Vec<_>>>()?;
+
+        match verifying
+            .iter()
+            .find(|key| key.kid.as_deref() == Some(kid.as_str()))
+        {
+            Some(key) if key.algorithm == algorithm => {}
+            Some(_) => {
+                return Err(Error::InvalidKey(format!(
+                    "{kid} does not match JWT_ALGORITHM"
+                )))
+            }
+            None => return Err(Error::InvalidKey(format!("{kid} is not in JWT_JWKS"))),
+        }
+
+        Ok(Self {
+            kid: Some(kid),
+            algorithm,
+            signing,
+            verifying,
+            jwks,
+        })
+    }
+
+    /// RSA keys can sign with several digests, so their JWK must name the one
+    /// it is used with instead of assuming RS256.
+    fn verifying_key(jwk: &Jwk) -> Result<VerifyingKey> {
+        let algorithm = match (&jwk.algorithm, jwk.common.key_algorithm) {
+            (AlgorithmParameters::OctetKeyPair(_), None | Some(KeyAlgorithm::EdDSA)) => {
+                Algorithm::EdDSA
+            }
+            (AlgorithmParameters::RSA(_), Some(KeyAlgorithm::RS256)) => Algorithm::RS256,
+            (AlgorithmParameters::RSA(_), Some(KeyAlgorithm::RS384)) => Algorithm::RS384,
+            (AlgorithmParameters::RSA(_), Some(KeyAlgorithm::RS512)) => Algorithm::RS512,
+            _ => {
+                return Err(Error::InvalidKey(format!(
+                    "{:?} has an unsupported or missing algorithm",
+                    jwk.common.key_id
+                )))
+            }
+        };
+
+        Ok(VerifyingKey {
+            kid: jwk.common.key_id.clone(),
+            algorithm,
+            key: DecodingKey::from_jwk(jwk)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rsa(alg: Option<&str>) -> Jwk {
+        let mut jwk = serde_json::json!({
+            "kty": "RSA",
+            "kid": "rsa",
+            "n": "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri23bOdgWp4Dy1WlUzewbgBHod5pcM9H95GQRV3JDXboIRROSBigeC5yjU1hGzHHyXss8UDprecbAYxknTcQkhslANGRUZmdTOQ5qTRsLAt6BTYuyvVRdhS8exSZEy_c4gs_7svlJJQ4H9_NxsiIoLwAEk7-Q3UXERGYw_75IDrGA84-lA_-Ct4eTlXHBIY2EaV7t7LjJaynVJCpkv4LKjTTAumiGUIuQhrNhZLuF_RJLqHpM2kgWFLU7-VTdL1VbC2tejvcI2BlMkEpk1BzBZI0KQB0GaDWFLN-aEAw3vRw",
+            "e": "AQAB"
+        });
+
+        if let Some(alg) = alg {
+            jwk["alg"] = alg.into();
+        }
+
+        serde_json::from_value(jwk).unwrap()
+    }
+
+    #[test]
+    fn rsa_keys_use_their_declared_algorithm() {
+        for (alg, algorithm) in [
+            ("RS256", Algorithm::RS256),
+            ("RS384", Algorithm::RS384),
+            ("RS512", Algorithm::RS512),
+        ] {
+            let key = Keys::verifying_key(&rsa(Some(alg))).unwrap();
+
+            assert_eq!(key.algorithm, algorithm);
+        }
+    }
+
+    #[test]
+    fn rsa_keys_without_an_algorithm_are_rejected() {
+        assert!(matches!(
+            Keys::verifying_key(&rsa(None)),
+            Err(Error::InvalidKey(_))
+        ));
+        assert!(matches!(
+            Keys::verifying_key(&rsa(Some("PS256"))),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+}
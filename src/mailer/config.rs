@@ -0,0 +1,26 @@
+use crate::miscs::get_env;
+use crate::Result;
+
+#[allow(non_snake_case)]
+pub struct Config {
+    pub KIND: String,
+    pub FROM: String,
+    pub FILE: Option<String>,
+    pub SMTP_HOST: Option<String>,
+    pub SMTP_USER: Option<String>,
+    pub SMTP_PASS: Option<String>,
+}
+
+impl Config {
+    pub fn load_from_env() -> Result<Self> {
+        Ok(Self {
+            KIND: get_env("MAILER")?,
+            FROM: get_env("MAIL_FROM")
+                .unwrap_or_else(|_| "Basher <no-reply@basher.dcism.org>".to_string()),
+            FILE: get_env("MAIL_FILE").ok(),
+            SMTP_HOST: get_env("SMTP_HOST").ok(),
+            SMTP_USER: get_env("SMTP_USER").ok(),
+            SMTP_PASS: get_env("SMTP_PASS").ok(),
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use crate::Result;
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+pub type SharedMailer = Arc<dyn Mailer>;
+
+pub trait Mailer: Send + Sync {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<()>>;
+}
+
+#[derive(Clone, Debug)]
+pub struct Mail {
+    to: String,
+    subject: String,
+    body: String,
+}
+
+impl Mail {
+    pub fn new(to: &str, subject: &str, body: String) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body,
+        }
+    }
+
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
@@ -0,0 +1,51 @@
+use super::defs::{Mail, Mailer};
+use crate::Result;
+
+use futures::future::BoxFuture;
+use tokio::io::AsyncWriteExt;
+
+pub struct FileMailer {
+    path: Option<String>,
+}
+
+impl FileMailer {
+    pub fn new(path: Option<String>) -> Self {
+        Self { path }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let content = format!(
+                "To: {}\nSubject: {}\n\n{}\n\n",
+                mail.to(),
+                mail.subject(),
+                mail.body()
+            );
+
+            match &self.path {
+                Some(path) => {
+                    let mut file = tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .await?;
+
+                    file.write_all(content.as_bytes()).await?;
+                }
+                None => {
+                    let mut stdout = tokio::io::stdout();
+
+                    stdout.write_all(content.as_bytes()).await?;
+                    stdout.flush().await?;
+                }
+            }
+
+            // Temporary
+            tracing::debug!(to = %mail.to(), "Mail written");
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,77 @@
+pub mod defs;
+pub mod file;
+
+mod config;
+mod smtp;
+
+use crate::{Error, Result};
+
+use self::config::Config;
+use self::defs::SharedMailer;
+use self::file::FileMailer;
+use self::smtp::SmtpMailer;
+use std::sync::Arc;
+
+pub fn get_mailer() -> Result<SharedMailer> {
+    let cfg = Config::load_from_env()?;
+    let mailer = from_config(&cfg)?;
+
+    let span = tracing::debug_span!("Mailer");
+    let _enter = span.enter();
+
+    tracing::debug!(kind = &cfg.KIND, "Using");
+
+    Ok(mailer)
+}
+
+fn from_config(cfg: &Config) -> Result<SharedMailer> {
+    let mailer: SharedMailer = match cfg.KIND.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(cfg)?),
+        "file" => {
+            let path = cfg
+                .FILE
+                .clone()
+                .ok_or_else(|| Error::MissingEnv("MAIL_FILE".to_string()))?;
+
+            Arc::new(FileMailer::new(Some(path)))
+        }
+        "stdout" => Arc::new(FileMailer::new(None)),
+        kind => return Err(Error::InvalidEnv(format!("MAILER={kind}"))),
+    };
+
+    Ok(mailer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(kind: &str, file: Option<&str>) -> Config {
+        Config {
+            KIND: kind.to_string(),
+            FROM: "Basher <no-reply@localhost>".to_string(),
+            FILE: file.map(str::to_string),
+            SMTP_HOST: None,
+            SMTP_USER: None,
+            SMTP_PASS: None,
+        }
+    }
+
+    #[test]
+    fn unknown_mailer_is_rejected() {
+        assert!(matches!(
+            from_config(&config("smpt", None)),
+            Err(Error::InvalidEnv(_))
+        ));
+    }
+
+    #[test]
+    fn file_mailer_requires_a_path() {
+        assert!(matches!(
+            from_config(&config("file", None)),
+            Err(Error::MissingEnv(_))
+        ));
+        assert!(from_config(&config("file", Some("mail.log"))).is_ok());
+        assert!(from_config(&config("stdout", None)).is_ok());
+    }
+}
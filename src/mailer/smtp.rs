@@ -0,0 +1,63 @@
+use super::{
+    config::Config,
+    defs::{Mail, Mailer},
+};
+use crate::{Error, Result};
+
+use futures::future::BoxFuture;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(cfg: &Config) -> Result<Self> {
+        let host = cfg
+            .SMTP_HOST
+            .as_deref()
+            .ok_or_else(|| Error::MissingEnv("SMTP_HOST".to_string()))?;
+        let user = cfg
+            .SMTP_USER
+            .clone()
+            .ok_or_else(|| Error::MissingEnv("SMTP_USER".to_string()))?;
+        let pass = cfg
+            .SMTP_PASS
+            .clone()
+            .ok_or_else(|| Error::MissingEnv("SMTP_PASS".to_string()))?;
+
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(host)?
+            .credentials(Credentials::new(user, pass))
+            .build();
+
+        Ok(Self {
+            from: cfg.FROM.parse()?,
+            transport,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(mail.to().parse()?)
+                .subject(mail.subject())
+                .header(ContentType::TEXT_PLAIN)
+                .body(mail.body().to_string())?;
+
+            self.transport.send(message).await?;
+
+            // Temporary
+            tracing::debug!(to = %mail.to(), "Mail sent");
+
+            Ok(())
+        })
+    }
+}
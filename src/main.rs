@@ -1,23 +1,35 @@
 mod auth;
 mod config;
+mod csrf;
 mod db;
 mod error;
 mod graphql;
+mod keys;
+mod mailer;
 mod miscs;
+mod oidc;
+mod password;
 mod sse;
+#[cfg(test)]
+mod test_utils;
+mod throttle;
+mod two_factor;
 
 pub use crate::config::config;
 pub use crate::error::{ClientError, Error, Result};
 
 use axum::body::Body;
 use axum::http::{header, Method, Request, Response};
+use axum::routing::get;
 use axum::Router;
 use futures::lock::Mutex;
-use shuttle_runtime::SecretStore;
+use shuttle_runtime::{CustomError, SecretStore};
 use sse::defs::{ReplyTX, SharedReplyChannels, TopicData};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::net::TcpListener;
 use tokio::sync::broadcast;
 use tower_http::classify::ServerErrorsFailureClass;
 use tower_http::cors::CorsLayer;
@@ -26,7 +38,10 @@ use tower_http::trace::TraceLayer;
 use tracing::Span;
 
 async fn app() -> Result<Router> {
+    keys::init()?;
+
     let db = Arc::new(db::get_connection().await?);
+    let mailer = mailer::get_mailer()?;
 
     let (topic_tx, _rx) = broadcast::channel::<TopicData>(1);
     let topic_tx = Arc::new(topic_tx);
@@ -37,8 +52,10 @@ async fn app() -> Result<Router> {
     let serve_dir = ServeDir::new("dist").fallback(ServeFile::new("dist/index.html"));
 
     Ok(Router::new()
+        .route("/.well-known/jwks.json", get(keys::jwks_handler))
+        .nest("/auth/oidc", oidc::router(&db)?)
         .nest("/sse", sse::router(&topic_tx, &reply_channels))
-        .nest("/graphql", graphql::router(&db, &topic_tx, &reply_channels))
+        .nest("/graphql", graphql::router(&db, &mailer, &topic_tx, &reply_channels))
         .fallback_service(serve_dir)
         .layer(
             TraceLayer::new_for_http()
@@ -61,15 +78,45 @@ async fn app() -> Result<Router> {
         ).layer(
             CorsLayer::new()
                 .allow_origin(axum::http::HeaderValue::from_static("https://basher.dcism.org"))
-                .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
+                .allow_headers([
+                    header::AUTHORIZATION,
+                    header::CONTENT_TYPE,
+                    header::ACCEPT,
+                    header::HeaderName::from_static(csrf::Csrf::HEADER_NAME),
+                ])
                 .allow_methods([Method::GET, Method::POST])
                 .allow_credentials(true)
         )
     )
 }
 
+/// Serves the router with `ConnectInfo`, which `shuttle_axum::AxumService`
+/// leaves out, so the login throttle can key on the peer address.
+struct Server(Router);
+
+async fn serve(listener: TcpListener, router: Router) -> std::io::Result<()> {
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+}
+
+#[shuttle_runtime::async_trait]
+impl shuttle_runtime::Service for Server {
+    async fn bind(self, addr: SocketAddr) -> std::result::Result<(), shuttle_runtime::Error> {
+        let listener = TcpListener::bind(addr).await.map_err(CustomError::new)?;
+
+        serve(listener, self.0).await.map_err(CustomError::new)?;
+
+        Ok(())
+    }
+}
+
 #[shuttle_runtime::main]
-async fn main(#[shuttle_runtime::Secrets] secrets: SecretStore) -> shuttle_axum::ShuttleAxum {
+async fn main(
+    #[shuttle_runtime::Secrets] secrets: SecretStore,
+) -> std::result::Result<Server, shuttle_runtime::Error> {
     secrets.into_iter().for_each(|(key, value)| {
         std::env::set_var(key, value);
     });
@@ -80,5 +127,56 @@ async fn main(#[shuttle_runtime::Secrets] secrets: SecretStore) -> shuttle_axum:
 
     let app = app().await.unwrap();
 
-    Ok(app.into())
+    Ok(Server(app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailer::{defs::SharedMailer, file::FileMailer};
+
+    #[tokio::test]
+    async fn login_throttle_sees_the_peer_address() {
+        let db = test_utils::db().await;
+
+        let mailer: SharedMailer = Arc::new(FileMailer::new(None));
+        let (topic_tx, _rx) = broadcast::channel::<TopicData>(1);
+        let reply_channels: SharedReplyChannels = Arc::new(Mutex::new(HashMap::new()));
+
+        let router = Router::new().nest(
+            "/graphql",
+            graphql::router(&db, &mailer, &Arc::new(topic_tx), &reply_channels),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+
+        tokio::spawn(serve(listener, router));
+
+        let client = reqwest::Client::new();
+
+        // Every attempt uses a new email, so only the per-address limit can
+        // lock them out, and only if the handler resolved the peer address.
+        for attempt in 0..50 {
+            let query = format!(
+                r#"mutation {{ user {{ login(input: {{ email: "user{attempt}@example.com", password: "password", rememberMe: false }}) {{ challenge }} }} }}"#
+            );
+
+            let response = client
+                .post(&url)
+                .json(&serde_json::json!({ "query": query }))
+                .send()
+                .await
+                .unwrap()
+                .json::<serde_json::Value>()
+                .await
+                .unwrap();
+
+            if response["errors"][0]["message"] == "TOO_MANY_ATTEMPTS" {
+                return;
+            }
+        }
+
+        panic!("Login attempts were never throttled by address");
+    }
 }
@@ -1,5 +1,15 @@
 use crate::{Error, Result};
 
+use rand::{distributions::Alphanumeric, Rng};
+
 pub fn get_env(name: &str) -> Result<String> {
     std::env::var(name).map_err(|_| Error::MissingEnv(name.to_string()))
 }
+
+pub fn generate_token(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
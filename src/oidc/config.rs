@@ -0,0 +1,26 @@
+use crate::miscs::get_env;
+use crate::{config, Result};
+
+#[allow(non_snake_case)]
+pub struct Config {
+    pub ISSUER: Option<String>,
+    pub CLIENT_ID: String,
+    pub CLIENT_SECRET: Option<String>,
+    pub REDIRECT_URL: String,
+    pub SCOPES: String,
+}
+
+impl Config {
+    pub fn load_from_env() -> Result<Self> {
+        Ok(Self {
+            ISSUER: get_env("OIDC_ISSUER")
+                .ok()
+                .map(|issuer| issuer.trim_end_matches('/').to_string()),
+            CLIENT_ID: get_env("OIDC_CLIENT_ID").unwrap_or_default(),
+            CLIENT_SECRET: get_env("OIDC_CLIENT_SECRET").ok(),
+            REDIRECT_URL: get_env("OIDC_REDIRECT_URL")
+                .unwrap_or_else(|_| format!("{}/auth/oidc/callback", config().APP_URL)),
+            SCOPES: get_env("OIDC_SCOPES").unwrap_or_else(|_| "openid email".to_string()),
+        })
+    }
+}
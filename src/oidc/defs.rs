@@ -0,0 +1,48 @@
+use super::provider::Provider;
+
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub type SharedProvider = Arc<Provider>;
+
+#[derive(Deserialize)]
+pub struct Discovery {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+pub struct TokenResponse {
+    pub id_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct IdClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub nonce: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LoginParams {
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+#[derive(Deserialize)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PendingLogin {
+    pub verifier: String,
+    pub nonce: String,
+    pub remember_me: bool,
+}
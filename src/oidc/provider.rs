@@ -0,0 +1,198 @@
+use super::config::Config;
+use super::defs::{Discovery, IdClaims, TokenResponse};
+use crate::{ClientError, Error, Result};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    jwk::{Jwk, JwkSet},
+    DecodingKey, Validation,
+};
+use sha2::{Digest, Sha256};
+use tokio::sync::{OnceCell, RwLock};
+
+pub struct Provider {
+    issuer: String,
+    cfg: Config,
+    client: reqwest::Client,
+    discovery: OnceCell<Discovery>,
+    jwks: RwLock<Option<JwkSet>>,
+}
+
+impl Provider {
+    pub fn new(issuer: String, cfg: Config) -> Self {
+        Self {
+            issuer,
+            cfg,
+            client: reqwest::Client::new(),
+            discovery: OnceCell::new(),
+            jwks: RwLock::new(None),
+        }
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    pub async fn authorize_url(&self, state: &str, nonce: &str, verifier: &str) -> Result<String> {
+        let discovery = self.discovery().await?;
+
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        let request = self
+            .client
+            .get(&discovery.authorization_endpoint)
+            .query(&[
+                ("response_type", "code"),
+                ("client_id", &self.cfg.CLIENT_ID),
+                ("redirect_uri", &self.cfg.REDIRECT_URL),
+                ("scope", &self.cfg.SCOPES),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", &challenge),
+                ("code_challenge_method", "S256"),
+            ])
+            .build()?;
+
+        Ok(request.url().to_string())
+    }
+
+    pub async fn exchange(&self, code: &str, verifier: &str, nonce: &str) -> Result<IdClaims> {
+        let discovery = self.discovery().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.cfg.REDIRECT_URL),
+            ("client_id", &self.cfg.CLIENT_ID),
+            ("code_verifier", verifier),
+        ];
+
+        if let Some(secret) = &self.cfg.CLIENT_SECRET {
+            form.push(("client_secret", secret));
+        }
+
+        let response = self
+            .client
+            .post(&discovery.token_endpoint)
+            .form(&form)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            // Temporary
+            tracing::debug!(status = %response.status(), "Code exchange rejected");
+
+            return Err(Error::Client(ClientError::Unauthorized));
+        }
+
+        let tokens = response.json::<TokenResponse>().await?;
+
+        let claims = self.verify(discovery, &tokens.id_token).await?;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            // Temporary
+            tracing::debug!("Nonce mismatch");
+
+            return Err(Error::Client(ClientError::Unauthorized));
+        }
+
+        Ok(claims)
+    }
+
+    async fn verify(&self, discovery: &Discovery, id_token: &str) -> Result<IdClaims> {
+        let header = jsonwebtoken::decode_header(id_token)
+            .map_err(|_| Error::Client(ClientError::Unauthorized))?;
+
+        let Some(jwk) = self.jwk(discovery, header.kid.as_deref()).await? else {
+            // Temporary
+            tracing::debug!("Signing key not found");
+
+            return Err(Error::Client(ClientError::Unauthorized));
+        };
+
+        let mut validation = Validation::new(header.alg);
+
+        validation.set_audience(&[&self.cfg.CLIENT_ID]);
+        validation.set_issuer(&[&discovery.issuer]);
+
+        let key = DecodingKey::from_jwk(&jwk)?;
+
+        match jsonwebtoken::decode::<IdClaims>(id_token, &key, &validation) {
+            Ok(data) => Ok(data.claims),
+            Err(err) => {
+                // Temporary
+                tracing::debug!(%err, "ID token validation failed");
+
+                Err(Error::Client(ClientError::Unauthorized))
+            }
+        }
+    }
+
+    /// Serves keys from the cached set and only refetches it when the token
+    /// names a key the cache does not have yet, e.g. after a rotation.
+    async fn jwk(&self, discovery: &Discovery, kid: Option<&str>) -> Result<Option<Jwk>> {
+        if let Some(jwk) = self
+            .jwks
+            .read()
+            .await
+            .as_ref()
+            .and_then(|jwks| find(jwks, kid))
+        {
+            return Ok(Some(jwk));
+        }
+
+        // Temporary
+        tracing::debug!("Fetching signing keys");
+
+        let jwks = self
+            .client
+            .get(&discovery.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<JwkSet>()
+            .await?;
+
+        let jwk = find(&jwks, kid);
+
+        *self.jwks.write().await = Some(jwks);
+
+        Ok(jwk)
+    }
+
+    async fn discovery(&self) -> Result<&Discovery> {
+        self.discovery
+            .get_or_try_init(|| async {
+                // Temporary
+                tracing::debug!(issuer = %self.issuer, "Fetching discovery document");
+
+                let url = format!("{}/.well-known/openid-configuration", self.issuer);
+
+                let discovery = self
+                    .client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<Discovery>()
+                    .await?;
+
+                if discovery.issuer.trim_end_matches('/') != self.issuer {
+                    return Err(Error::InvalidEnv(format!(
+                        "OIDC_ISSUER does not match the discovered issuer {}",
+                        discovery.issuer
+                    )));
+                }
+
+                Ok(discovery)
+            })
+            .await
+    }
+}
+
+fn find(jwks: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None => jwks.keys.first().cloned(),
+    }
+}
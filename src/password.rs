@@ -0,0 +1,143 @@
+use crate::{config, miscs::generate_token, Result};
+
+use argon2::{
+    password_hash::{
+        self, rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Algorithm, Argon2, Params, Version,
+};
+use std::sync::OnceLock;
+
+pub struct Password;
+
+impl Password {
+    pub fn hash(password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+
+        Ok(Self::argon2()?
+            .hash_password(password.as_bytes(), &salt)?
+            .to_string())
+    }
+
+    pub fn verify(password: &str, hash: &str) -> Result<bool> {
+        if hash.is_empty() {
+            Self::verify_dummy(password);
+
+            return Ok(false);
+        }
+
+        // Every verification pays for both algorithms in this mode, so legacy
+        // bcrypt accounts take as long as argon2 ones and as unknown emails.
+        let pad = config().HIDE_ACCOUNT_EXISTENCE;
+
+        if Self::is_bcrypt(hash) {
+            if pad {
+                Self::dummy_argon2(password);
+            }
+
+            return Ok(bcrypt::verify(password, hash)?);
+        }
+
+        if pad {
+            Self::dummy_bcrypt(password);
+        }
+
+        let hash = PasswordHash::new(hash)?;
+
+        match Self::argon2()?.verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(true),
+            Err(password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn needs_rehash(hash: &str) -> Result<bool> {
+        if Self::is_bcrypt(hash) {
+            return Ok(true);
+        }
+
+        let cfg = config();
+
+        let hash = PasswordHash::new(hash)?;
+        let params = Params::try_from(&hash)?;
+
+        Ok(hash.algorithm != Algorithm::Argon2id.ident()
+            || params.m_cost() != cfg.ARGON2_MEMORY_KIB
+            || params.t_cost() != cfg.ARGON2_ITERATIONS
+            || params.p_cost() != cfg.ARGON2_PARALLELISM)
+    }
+
+    pub fn verify_dummy(password: &str) {
+        Self::dummy_argon2(password);
+
+        if config().HIDE_ACCOUNT_EXISTENCE {
+            Self::dummy_bcrypt(password);
+        }
+    }
+
+    fn dummy_argon2(password: &str) {
+        static HASH: OnceLock<String> = OnceLock::new();
+
+        let hash = HASH.get_or_init(|| Self::hash(&generate_token(32)).unwrap_or_default());
+
+        if let (Ok(argon2), Ok(hash)) = (Self::argon2(), PasswordHash::new(hash)) {
+            let _ = argon2.verify_password(password.as_bytes(), &hash);
+        }
+    }
+
+    fn dummy_bcrypt(password: &str) {
+        static HASH: OnceLock<String> = OnceLock::new();
+
+        let hash = HASH.get_or_init(|| {
+            bcrypt::hash(generate_token(32), bcrypt::DEFAULT_COST).unwrap_or_default()
+        });
+
+        if !hash.is_empty() {
+            let _ = bcrypt::verify(password, hash);
+        }
+    }
+
+    fn is_bcrypt(hash: &str) -> bool {
+        hash.starts_with("$2")
+    }
+
+    fn argon2() -> Result<Argon2<'static>> {
+        let cfg = config();
+
+        let params = Params::new(
+            cfg.ARGON2_MEMORY_KIB,
+            cfg.ARGON2_ITERATIONS,
+            cfg.ARGON2_PARALLELISM,
+            None,
+        )?;
+
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_bcrypt_hashes_verify_and_need_rehash() {
+        crate::test_utils::init();
+
+        let hash = bcrypt::hash("password", 4).unwrap();
+
+        assert!(Password::verify("password", &hash).unwrap());
+        assert!(!Password::verify("wrong", &hash).unwrap());
+        assert!(Password::needs_rehash(&hash).unwrap());
+    }
+
+    #[test]
+    fn current_hashes_do_not_need_rehash() {
+        crate::test_utils::init();
+
+        let hash = Password::hash("password").unwrap();
+
+        assert!(Password::verify("password", &hash).unwrap());
+        assert!(!Password::needs_rehash(&hash).unwrap());
+        assert!(!Password::verify("password", "").unwrap());
+    }
+}
@@ -14,11 +14,15 @@ pub type SharedReplyChannels = Arc<Mutex<HashMap<String, ReplyTX>>>;
 #[derive(Serialize, Clone, Debug)]
 pub struct TopicData {
     id: ID,
+    kind: String,
 }
 
 impl TopicData {
-    pub fn new(id: ID) -> Self {
-        Self { id }
+    pub fn new(id: ID, kind: &str) -> Self {
+        Self {
+            id,
+            kind: kind.to_string(),
+        }
     }
 }
 
@@ -16,6 +16,8 @@ use tokio_stream::{
 
 use super::defs::SharedReplyChannels;
 
+// Subscribers are anonymous, so identities a user muted are still announced
+// here; the reply queries flag them with `is_muted` when clients refetch.
 pub async fn handler(
     Path(id): Path<String>,
     Extension(channels): Extension<SharedReplyChannels>,
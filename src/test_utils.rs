@@ -0,0 +1,109 @@
+use crate::db::defs::{DBQuery, SharedDB};
+use crate::db::table::User;
+use crate::graphql::{GuardedUser, RootMutation, RootQuery};
+use crate::mailer::defs::SharedMailer;
+use crate::sse::defs::{SharedReplyChannels, TopicData};
+use crate::throttle::ClientIp;
+
+use async_graphql::{EmptySubscription, Request, Schema};
+use axum_extra::headers::{Authorization, UserAgent};
+use axum_extra::TypedHeader;
+use futures::lock::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Once};
+use tokio::sync::broadcast;
+use tower_cookies::Cookies;
+
+pub const EMAIL: &str = "alice@example.com";
+
+/// Sets the environment every test shares before `config()` is first read.
+pub fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        for (key, value) in [
+            ("JWT_SECRET", "test secret"),
+            ("APP_URL", "http://localhost:5173"),
+            ("ARGON2_MEMORY_KIB", "64"),
+            ("ARGON2_ITERATIONS", "1"),
+            ("ARGON2_PARALLELISM", "1"),
+        ] {
+            if std::env::var(key).is_err() {
+                std::env::set_var(key, value);
+            }
+        }
+    });
+}
+
+/// Connects to a fresh in-memory database with the schema imported, or to
+/// the SurrealDB instance in `SURREAL_TEST_URL` when one is configured.
+pub async fn db() -> SharedDB {
+    init();
+
+    let url = std::env::var("SURREAL_TEST_URL").unwrap_or_else(|_| "mem://".to_string());
+
+    let db = surrealdb::engine::any::connect(url.as_str())
+        .await
+        .expect("Could not connect to SurrealDB");
+
+    if url != "mem://" {
+        let user = std::env::var("SURREAL_TEST_USER").unwrap_or_else(|_| "root".to_string());
+        let pass = std::env::var("SURREAL_TEST_PASS").unwrap_or_else(|_| "root".to_string());
+
+        db.signin(surrealdb::opt::auth::Root {
+            username: &user,
+            password: &pass,
+        })
+        .await
+        .expect("Could not sign in to SurrealDB");
+    }
+
+    db.use_ns("test")
+        .use_db(surrealdb::sql::Id::rand().to_raw())
+        .await
+        .expect("Could not use database");
+
+    db.import(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/database/schema.surql"
+    ))
+    .await
+    .expect("Could not import schema");
+
+    Arc::new(db)
+}
+
+/// Creates the account most tests act as, with `EMAIL` and the given hash.
+pub async fn user(db: &SharedDB, password: &str) -> User {
+    db.query(DBQuery::CREATE_USER)
+        .bind(("email", EMAIL))
+        .bind(("password", password.to_owned()))
+        .await
+        .expect("Could not create user")
+        .take::<Option<User>>(0)
+        .expect("Could not create user")
+        .expect("Could not create user")
+}
+
+/// Builds the GraphQL schema the router serves, without any request data.
+pub fn schema(
+    db: &SharedDB,
+    mailer: &SharedMailer,
+) -> Schema<RootQuery, RootMutation, EmptySubscription> {
+    let (topic_tx, _rx) = broadcast::channel::<TopicData>(1);
+    let reply_channels: SharedReplyChannels = Arc::new(Mutex::new(HashMap::new()));
+
+    crate::graphql::schema(db, mailer, &Arc::new(topic_tx), &reply_channels)
+}
+
+/// Attaches the per-request data the GraphQL handler normally provides.
+pub fn request(query: &str, bearer: Option<&str>) -> Request {
+    let auth_header = bearer.map(|token| TypedHeader(Authorization::bearer(token).unwrap()));
+
+    Request::new(query)
+        .data(Cookies::default())
+        .data(auth_header)
+        .data(None::<TypedHeader<UserAgent>>)
+        .data(ClientIp::default())
+        .data(GuardedUser::default())
+}
@@ -0,0 +1,240 @@
+use crate::{
+    db::defs::{DBQuery, DBTable, SharedDB},
+    ClientError, Error, Result,
+};
+
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use std::net::SocketAddr;
+use surrealdb::sql::Thing;
+
+#[derive(Clone, Default)]
+pub struct ClientIp(Option<String>);
+
+impl ClientIp {
+    /// Forwarding headers are only honoured behind a trusted proxy, and then
+    /// only the rightmost hop, which the proxy appended itself. Everything to
+    /// its left was sent by the client.
+    pub fn resolve(headers: &HeaderMap, peer: Option<SocketAddr>, trust_proxy: bool) -> Self {
+        if !trust_proxy {
+            return Self(peer.map(|addr| addr.ip().to_string()));
+        }
+
+        let ip = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .or_else(|| {
+                headers
+                    .get("x-real-ip")
+                    .and_then(|value| value.to_str().ok())
+            })
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+
+        Self(ip)
+    }
+
+    pub fn as_deref(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+pub struct LoginThrottle;
+
+impl LoginThrottle {
+    const ACCOUNT_THRESHOLD: u64 = 5;
+    const IP_THRESHOLD: u64 = 20;
+    const GUEST_THRESHOLD: u64 = 10;
+
+    const BASE_LOCK_SECONDS: u64 = 30;
+    const MAX_LOCK_SECONDS: u64 = 60 * 60;
+
+    fn account(email: &str) -> Thing {
+        Thing::from((DBTable::LOGIN_ATTEMPT, format!("email:{email}").as_str()))
+    }
+
+    fn ip(ip: &str) -> Thing {
+        Thing::from((DBTable::LOGIN_ATTEMPT, format!("ip:{ip}").as_str()))
+    }
+
+    fn guest(ip: &str) -> Thing {
+        Thing::from((DBTable::LOGIN_ATTEMPT, format!("guest:{ip}").as_str()))
+    }
+
+    pub async fn check(db: &SharedDB, email: &str, ip: &ClientIp) -> Result<()> {
+        let mut attempts = vec![Self::account(email)];
+
+        if let Some(ip) = ip.as_deref() {
+            attempts.push(Self::ip(ip));
+        }
+
+        let mut response = db
+            .query(DBQuery::SELECT_LOCKED_LOGIN_ATTEMPTS)
+            .bind(("attempts", attempts))
+            .await?;
+
+        if !response.take::<Vec<DateTime<Utc>>>(0)?.is_empty() {
+            // Temporary
+            tracing::debug!("Login locked");
+
+            return Err(Error::Client(ClientError::TooManyAttempts));
+        }
+
+        Ok(())
+    }
+
+    pub async fn failure(db: &SharedDB, email: &str, ip: &ClientIp) -> Result<()> {
+        // Temporary
+        tracing::debug!("Recording failed login");
+
+        let mut attempts = vec![(Self::account(email), Self::ACCOUNT_THRESHOLD)];
+
+        if let Some(ip) = ip.as_deref() {
+            attempts.push((Self::ip(ip), Self::IP_THRESHOLD));
+        }
+
+        for (attempt, threshold) in attempts {
+            db.query(DBQuery::RECORD_LOGIN_FAILURE)
+                .bind(("attempt", attempt))
+                .bind(("threshold", threshold))
+                .bind(("base", Self::BASE_LOCK_SECONDS))
+                .bind(("max", Self::MAX_LOCK_SECONDS))
+                .await?
+                .check()?;
+        }
+
+        Ok(())
+    }
+
+    /// Counts guest accounts created per address with the same backoff as
+    /// failed logins. Without a known address all guests share one budget.
+    pub async fn guest_created(db: &SharedDB, ip: &ClientIp) -> Result<()> {
+        // Without an address every guest would share a single budget, and a
+        // handful of requests would lock guest mode for everyone.
+        let Some(ip) = ip.as_deref() else {
+            // Temporary
+            tracing::debug!("Guest creation refused without an address");
+
+            return Err(Error::Client(ClientError::Forbidden));
+        };
+
+        let attempt = Self::guest(ip);
+
+        let mut response = db
+            .query(DBQuery::SELECT_LOCKED_LOGIN_ATTEMPTS)
+            .bind(("attempts", vec![attempt.to_owned()]))
+            .await?;
+
+        if !response.take::<Vec<DateTime<Utc>>>(0)?.is_empty() {
+            // Temporary
+            tracing::debug!("Guest creation locked");
+
+            return Err(Error::Client(ClientError::TooManyAttempts));
+        }
+
+        db.query(DBQuery::RECORD_LOGIN_FAILURE)
+            .bind(("attempt", attempt))
+            .bind(("threshold", Self::GUEST_THRESHOLD))
+            .bind(("base", Self::BASE_LOCK_SECONDS))
+            .bind(("max", Self::MAX_LOCK_SECONDS))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub async fn success(db: &SharedDB, email: &str) -> Result<()> {
+        db.query(DBQuery::DELETE_LOGIN_ATTEMPT)
+            .bind(("attempt", Self::account(email)))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn headers(forwarded: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert("x-forwarded-for", forwarded.parse().unwrap());
+
+        headers
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_without_a_trusted_proxy() {
+        let peer = "10.0.0.1:443".parse().ok();
+
+        let ip = ClientIp::resolve(&headers("203.0.113.9"), peer, false);
+
+        assert_eq!(ip.as_deref(), Some("10.0.0.1"));
+        assert_eq!(
+            ClientIp::resolve(&headers("203.0.113.9"), None, false).as_deref(),
+            None
+        );
+    }
+
+    #[test]
+    fn trusted_proxy_uses_the_rightmost_hop() {
+        let ip = ClientIp::resolve(&headers("1.2.3.4, 198.51.100.7"), None, true);
+
+        assert_eq!(ip.as_deref(), Some("198.51.100.7"));
+    }
+
+    #[tokio::test]
+    async fn repeated_failures_lock_the_account() {
+        let db = test_utils::db().await;
+
+        let email = "alice@example.com";
+        let ip = ClientIp(Some("198.51.100.7".to_string()));
+
+        for _ in 0..LoginThrottle::ACCOUNT_THRESHOLD {
+            LoginThrottle::check(&db, email, &ip).await.unwrap();
+            LoginThrottle::failure(&db, email, &ip).await.unwrap();
+        }
+
+        assert!(matches!(
+            LoginThrottle::check(&db, email, &ip).await,
+            Err(Error::Client(ClientError::TooManyAttempts))
+        ));
+
+        // Other accounts from the same address stay below the IP threshold.
+        LoginThrottle::check(&db, "bob@example.com", &ip)
+            .await
+            .unwrap();
+
+        LoginThrottle::success(&db, email).await.unwrap();
+        LoginThrottle::check(&db, email, &ip).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn guests_are_capped_per_address() {
+        let db = test_utils::db().await;
+
+        let ip = ClientIp(Some("198.51.100.7".to_string()));
+
+        for _ in 0..LoginThrottle::GUEST_THRESHOLD {
+            LoginThrottle::guest_created(&db, &ip).await.unwrap();
+        }
+
+        assert!(matches!(
+            LoginThrottle::guest_created(&db, &ip).await,
+            Err(Error::Client(ClientError::TooManyAttempts))
+        ));
+
+        LoginThrottle::guest_created(&db, &ClientIp(Some("203.0.113.9".to_string())))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            LoginThrottle::guest_created(&db, &ClientIp::default()).await,
+            Err(Error::Client(ClientError::Forbidden))
+        ));
+    }
+}
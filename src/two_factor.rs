@@ -0,0 +1,171 @@
+use crate::{
+    db::defs::{DBQuery, SharedDB},
+    miscs::generate_token,
+    Result,
+};
+
+use async_graphql::Object;
+use rand::Rng;
+use std::time::{SystemTime, UNIX_EPOCH};
+use surrealdb::sql::Thing;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+pub struct TwoFactorSetup {
+    secret: String,
+    provisioning_uri: String,
+}
+
+#[Object]
+impl TwoFactorSetup {
+    async fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    async fn provisioning_uri(&self) -> &str {
+        &self.provisioning_uri
+    }
+}
+
+pub struct TwoFactor;
+
+impl TwoFactor {
+    const ISSUER: &'static str = "Basher";
+
+    const SECRET_LENGTH: usize = 20;
+    const RECOVERY_CODE_LENGTH: usize = 10;
+    const RECOVERY_CODE_COUNT: usize = 10;
+
+    pub fn generate_secret() -> String {
+        let bytes = rand::thread_rng().gen::<[u8; Self::SECRET_LENGTH]>();
+
+        Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+    }
+
+    pub fn setup(secret: &str, email: &str) -> Result<TwoFactorSetup> {
+        Ok(TwoFactorSetup {
+            secret: secret.to_string(),
+            provisioning_uri: Self::totp(secret, email)?.get_url(),
+        })
+    }
+
+    /// Accepts a code only if its time step is later than the last one the
+    /// user redeemed, so an observed code cannot be replayed within its window.
+    pub async fn check(
+        db: &SharedDB,
+        user: &Thing,
+        secret: &str,
+        email: &str,
+        code: &str,
+    ) -> Result<bool> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        let Some(step) = Self::step(secret, email, code, now)? else {
+            return Ok(false);
+        };
+
+        let mut response = db
+            .query(DBQuery::USE_TOTP_STEP)
+            .bind(("user", user.to_owned()))
+            .bind(("step", step))
+            .await?;
+
+        if response.take::<Option<Thing>>(0)?.is_none() {
+            // Temporary
+            tracing::debug!("Code already used");
+
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    pub fn recovery_codes() -> Vec<String> {
+        (0..Self::RECOVERY_CODE_COUNT)
+            .map(|_| generate_token(Self::RECOVERY_CODE_LENGTH).to_lowercase())
+            .collect()
+    }
+
+    fn step(secret: &str, email: &str, code: &str, time: u64) -> Result<Option<u64>> {
+        let mut totp = Self::totp(secret, email)?;
+
+        let current = time / totp.step;
+        let skew = totp.skew as u64;
+
+        totp.skew = 0;
+
+        Ok((current.saturating_sub(skew)..=current + skew)
+            .find(|step| totp.check(code.trim(), step * totp.step)))
+    }
+
+    fn totp(secret: &str, email: &str) -> Result<TOTP> {
+        Ok(TOTP::new(
+            Algorithm::SHA1,
+            6,
+            1,
+            30,
+            Secret::Encoded(secret.to_string()).to_bytes()?,
+            Some(Self::ISSUER.to_string()),
+            email.to_string(),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, EMAIL};
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn code(secret: &str, time: u64) -> String {
+        TwoFactor::totp(secret, EMAIL).unwrap().generate(time)
+    }
+
+    #[test]
+    fn codes_resolve_to_their_time_step() {
+        let secret = TwoFactor::generate_secret();
+
+        for offset in [-30, 0, 30] {
+            let time = NOW.checked_add_signed(offset).unwrap();
+
+            assert_eq!(
+                TwoFactor::step(&secret, EMAIL, &code(&secret, time), NOW).unwrap(),
+                Some(time / 30)
+            );
+        }
+
+        assert_eq!(
+            TwoFactor::step(&secret, EMAIL, &code(&secret, NOW - 90), NOW).unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn codes_cannot_be_replayed() {
+        let db = test_utils::db().await;
+
+        let secret = TwoFactor::generate_secret();
+
+        let user = test_utils::user(&db, "").await;
+
+        let totp = TwoFactor::totp(&secret, EMAIL).unwrap();
+        let current = totp.generate_current().unwrap();
+        let previous = totp.generate(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                - 30,
+        );
+
+        assert!(TwoFactor::check(&db, user.id(), &secret, EMAIL, &current)
+            .await
+            .unwrap());
+        assert!(!TwoFactor::check(&db, user.id(), &secret, EMAIL, &current)
+            .await
+            .unwrap());
+        assert!(!TwoFactor::check(&db, user.id(), &secret, EMAIL, &previous)
+            .await
+            .unwrap());
+    }
+}